@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+//How far a search is allowed to go. Unset limits are unlimited, so at least
+//one of them should be set
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchLimits
+{
+    pub max_depth: Option<u32>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>
+}
+
+impl SearchLimits
+{
+    pub fn depth( max_depth: u32 ) -> SearchLimits
+    {
+        SearchLimits{ max_depth: Some( max_depth ), time: None, nodes: None }
+    }
+
+    pub fn time( budget: Duration ) -> SearchLimits
+    {
+        SearchLimits{ max_depth: None, time: Some( budget ), nodes: None }
+    }
+
+    pub fn nodes( max_nodes: u64 ) -> SearchLimits
+    {
+        SearchLimits{ max_depth: None, time: None, nodes: Some( max_nodes ) }
+    }
+}
+
+//Checking the clock is relatively expensive, so it is only done every this many nodes
+const NODES_BETWEEN_CLOCK_CHECKS: u64 = 1024;
+
+//Keeps track of the resources used by a running search and tells it when to stop
+pub struct SearchControl
+{
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool
+}
+
+impl SearchControl
+{
+    pub fn new() -> SearchControl
+    {
+        SearchControl{ limits: SearchLimits::depth( 0 ), start: Instant::now(), nodes: 0, stopped: false }
+    }
+
+    pub fn start( &mut self, limits: SearchLimits )
+    {
+        *self = SearchControl{ limits, start: Instant::now(), nodes: 0, stopped: false };
+    }
+
+    pub fn limits( &self ) -> &SearchLimits
+    {
+        &self.limits
+    }
+
+    pub fn nodes( &self ) -> u64
+    {
+        self.nodes
+    }
+
+    pub fn elapsed( &self ) -> Duration
+    {
+        self.start.elapsed()
+    }
+
+    pub fn is_stopped( &self ) -> bool
+    {
+        self.stopped
+    }
+
+    //Counts a visited node, returns true if the search should stop
+    pub fn count_node( &mut self ) -> bool
+    {
+        self.nodes += 1;
+        if let Some( max_nodes ) = self.limits.nodes
+        {
+            if self.nodes > max_nodes
+            {
+                self.stopped = true;
+            }
+        }
+        if let Some( budget ) = self.limits.time
+        {
+            if self.nodes.is_multiple_of( NODES_BETWEEN_CLOCK_CHECKS ) && self.elapsed() >= budget
+            {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    //Each iteration of iterative deepening takes several times as long as the
+    //previous one, so there is no point in starting one after half of the time is gone
+    pub fn can_deepen( &self, depth: u32 ) -> bool
+    {
+        if self.stopped || self.limits.max_depth.is_some_and( |max_depth| depth > max_depth )
+        {
+            return false;
+        }
+        match self.limits.time
+        {
+            Some( budget ) => self.elapsed() < budget / 2,
+            None => true
+        }
+    }
+}
+
+impl Default for SearchControl
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn node_limit_stops()
+    {
+        let mut control = SearchControl::new();
+        control.start( SearchLimits::nodes( 10 ) );
+        for _ in 0..10
+        {
+            assert!( !control.count_node() );
+        }
+        assert!( control.count_node() );
+        assert!( control.is_stopped() );
+        assert!( !control.can_deepen( 1 ) );
+    }
+
+    #[test]
+    fn depth_limit_stops_deepening()
+    {
+        let mut control = SearchControl::new();
+        control.start( SearchLimits::depth( 3 ) );
+        assert!( control.can_deepen( 3 ) );
+        assert!( !control.can_deepen( 4 ) );
+        assert!( !control.count_node() );
+    }
+
+    #[test]
+    fn time_limit_stops()
+    {
+        let mut control = SearchControl::new();
+        control.start( SearchLimits::time( Duration::from_millis( 0 ) ) );
+        assert!( !control.can_deepen( 1 ) );
+        let stopped = ( 0..NODES_BETWEEN_CLOCK_CHECKS ).any( |_| control.count_node() );
+        assert!( stopped );
+    }
+}
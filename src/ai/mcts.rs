@@ -0,0 +1,338 @@
+//Monte Carlo tree search with the UCT selection rule. Only needs the rules of
+//the game, no evaluator: positions are valued by playing them out to the end.
+
+use std::f64;
+use std::mem;
+use std::time::{Duration, Instant};
+use game::GameSituation;
+use super::random::Random;
+
+//Chooses the moves of the simulated games
+pub trait PlayoutPolicy<Situation: GameSituation>
+{
+    fn choose_move( &mut self, situation: &Situation, moves: Vec<Situation::Move>, random: &mut Random ) -> Situation::Move;
+}
+
+//Plays uniformly random moves
+pub struct RandomPlayout{}
+
+impl<Situation: GameSituation> PlayoutPolicy<Situation> for RandomPlayout
+{
+    fn choose_move( &mut self, _situation: &Situation, mut moves: Vec<Situation::Move>, random: &mut Random ) -> Situation::Move
+    {
+        let index = random.below( moves.len() );
+        moves.swap_remove( index )
+    }
+}
+
+//Iterations of the default config, and of configs without limits
+const DEFAULT_ITERATIONS: u64 = 10000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct MctsConfig
+{
+    pub exploration: f64,
+    //The search stops when either of the limits runs out. Without either it
+    //stops after DEFAULT_ITERATIONS
+    pub iterations: Option<u64>,
+    pub time: Option<Duration>,
+    //None seeds from the clock
+    pub seed: Option<u64>
+}
+
+impl MctsConfig
+{
+    pub fn iterations( iterations: u64 ) -> MctsConfig
+    {
+        MctsConfig{ iterations: Some( iterations ), time: None, ..Default::default() }
+    }
+
+    pub fn time( budget: Duration ) -> MctsConfig
+    {
+        MctsConfig{ iterations: None, time: Some( budget ), ..Default::default() }
+    }
+}
+
+impl Default for MctsConfig
+{
+    fn default() -> Self
+    {
+        MctsConfig{ exploration: f64::consts::SQRT_2, iterations: Some( DEFAULT_ITERATIONS ), time: None, seed: None }
+    }
+}
+
+struct Node<Situation: GameSituation>
+{
+    situation: Situation,
+    //The move that led here from the parent
+    the_move: Option<Situation::Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_moves: Vec<Situation::Move>,
+    visits: u32,
+    //Sum of the results from the point of view of the player who made the_move
+    reward: f64
+}
+
+impl<Situation: GameSituation> Node<Situation>
+{
+    fn new( situation: Situation, the_move: Option<Situation::Move>, parent: Option<usize> ) -> Self
+    {
+        let untried_moves = situation.get_moves().collect();
+        Node{ situation, the_move, parent, children: Vec::new(), untried_moves, visits: 0, reward: 0.0 }
+    }
+}
+
+pub struct Mcts<Situation: GameSituation, Policy: PlayoutPolicy<Situation>>
+{
+    config: MctsConfig,
+    policy: Policy,
+    random: Random,
+    //Tree of the last search, the root is nodes[0]
+    nodes: Vec<Node<Situation>>,
+    //The move returned by the last search, used to find the subtree to reuse
+    last_move: Option<Situation::Move>
+}
+
+impl<Situation, Policy> Mcts<Situation, Policy>
+    where Situation: GameSituation + Clone, Situation::Move: Clone + PartialEq, Policy: PlayoutPolicy<Situation>
+{
+    pub fn new( config: MctsConfig, policy: Policy ) -> Self
+    {
+        let random = config.seed.map( Random::new ).unwrap_or_else( Random::from_clock );
+        Mcts{ config, policy, random, nodes: Vec::new(), last_move: None }
+    }
+
+    //Number of simulations the current tree is built from
+    pub fn root_visits( &self ) -> u32
+    {
+        self.nodes.first().map_or( 0, |root| root.visits )
+    }
+
+    //Returns the most visited move. previous_move is the opponent's reply to
+    //the move returned last time; if it is given the matching subtree of the
+    //old tree is kept.
+    pub fn search( &mut self, situation: &Situation, previous_move: Option<Situation::Move> ) -> Option<Situation::Move>
+    {
+        if situation.is_finished()
+        {
+            return None;
+        }
+        self.reuse_tree( situation, previous_move );
+        if self.nodes.is_empty()
+        {
+            self.nodes.push( Node::new( situation.clone(), None, None ) );
+        }
+
+        let limit = match self.config.time
+        {
+            Some( _ ) => self.config.iterations,
+            None => Some( self.config.iterations.unwrap_or( DEFAULT_ITERATIONS ) )
+        };
+        let start = Instant::now();
+        let mut iterations = 0;
+        while limit.is_none_or( |limit| iterations < limit ) &&
+              self.config.time.is_none_or( |budget| start.elapsed() < budget )
+        {
+            self.iterate();
+            iterations += 1;
+        }
+
+        let best_child = self.nodes[0].children.iter()
+            .max_by_key( |&&child| self.nodes[child].visits )
+            .cloned();
+        self.last_move = match best_child
+        {
+            Some( child ) => self.nodes[child].the_move.clone(),
+            None => self.nodes[0].untried_moves.first().cloned()
+        };
+        self.last_move.clone()
+    }
+
+    fn reuse_tree( &mut self, situation: &Situation, previous_move: Option<Situation::Move> )
+    {
+        let own_move = self.last_move.take();
+        let new_root = self.find_child( Some( 0 ), own_move ).and_then( |child| self.find_child( Some( child ), previous_move ) );
+        match new_root
+        {
+            Some( new_root ) if self.nodes[new_root].situation.hash_key() == situation.hash_key() => self.reroot( new_root ),
+            _ => self.nodes.clear()
+        }
+    }
+
+    fn find_child( &self, parent: Option<usize>, the_move: Option<Situation::Move> ) -> Option<usize>
+    {
+        let parent = self.nodes.get( parent? )?;
+        let the_move = the_move?;
+        parent.children.iter().cloned().find( |&child| self.nodes[child].the_move.as_ref() == Some( &the_move ) )
+    }
+
+    //Throws away everything but the subtree under new_root
+    fn reroot( &mut self, new_root: usize )
+    {
+        let mut old_nodes = mem::take( &mut self.nodes ).into_iter().map( Some ).collect::<Vec<_>>();
+        let mut stack = vec![ ( new_root, None ) ];
+        while let Some( ( old_index, parent ) ) = stack.pop()
+        {
+            let mut node = old_nodes[old_index].take().unwrap();
+            let new_index = self.nodes.len();
+            node.parent = parent;
+            if let Some( parent ) = parent
+            {
+                self.nodes[parent].children.push( new_index );
+            }
+            stack.extend( mem::take( &mut node.children ).into_iter().map( |child| ( child, Some( new_index ) ) ) );
+            self.nodes.push( node );
+        }
+        self.nodes[0].the_move = None;
+    }
+
+    //One round of selection, expansion, simulation and backpropagation
+    fn iterate( &mut self )
+    {
+        let mut current = 0;
+        while self.nodes[current].untried_moves.is_empty() && !self.nodes[current].children.is_empty()
+        {
+            current = self.select_child( current );
+        }
+
+        if !self.nodes[current].untried_moves.is_empty()
+        {
+            let index = self.random.below( self.nodes[current].untried_moves.len() );
+            let the_move = self.nodes[current].untried_moves.swap_remove( index );
+            let child = self.nodes[current].situation.copy_apply( the_move.clone() ).unwrap();
+            self.nodes.push( Node::new( child, Some( the_move ), Some( current ) ) );
+            let child_index = self.nodes.len() - 1;
+            self.nodes[current].children.push( child_index );
+            current = child_index;
+        }
+
+        let winner = self.playout( current );
+
+        let mut node = Some( current );
+        while let Some( index ) = node
+        {
+            let parent = self.nodes[index].parent;
+            let reward = match ( parent, &winner )
+            {
+                ( None, _ ) => 0.0,
+                ( Some( _ ), None ) => 0.5,
+                ( Some( parent ), Some( winner ) ) => if *winner == self.nodes[parent].situation.get_turn() { 1.0 } else { 0.0 }
+            };
+            self.nodes[index].visits += 1;
+            self.nodes[index].reward += reward;
+            node = parent;
+        }
+    }
+
+    fn select_child( &self, parent: usize ) -> usize
+    {
+        let log_visits = ( self.nodes[parent].visits as f64 ).ln();
+        let exploration = self.config.exploration;
+        let uct = |child: usize|
+        {
+            let node = &self.nodes[child];
+            let visits = node.visits as f64;
+            node.reward / visits + exploration * ( log_visits / visits ).sqrt()
+        };
+        let mut best = self.nodes[parent].children[0];
+        let mut best_value = uct( best );
+        for &child in &self.nodes[parent].children[1..]
+        {
+            let value = uct( child );
+            if value > best_value
+            {
+                best = child;
+                best_value = value;
+            }
+        }
+        best
+    }
+
+    fn playout( &mut self, node: usize ) -> Option<Situation::Role>
+    {
+        let situation = &self.nodes[node].situation;
+        if situation.is_finished()
+        {
+            return situation.get_winner();
+        }
+        let the_move = self.policy.choose_move( situation, situation.get_moves().collect(), &mut self.random );
+        let mut current = situation.copy_apply( the_move ).unwrap();
+        while !current.is_finished()
+        {
+            let the_move = self.policy.choose_move( &current, current.get_moves().collect(), &mut self.random );
+            current = current.copy_apply( the_move ).unwrap();
+        }
+        current.get_winner()
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use othello::OthelloSituation;
+
+    fn search_with_seed( seed: u64 ) -> Option<<OthelloSituation as GameSituation>::Move>
+    {
+        let config = MctsConfig{ seed: Some( seed ), ..MctsConfig::iterations( 200 ) };
+        Mcts::new( config, RandomPlayout{} ).search( &OthelloSituation::new(), None )
+    }
+
+    #[test]
+    fn search_is_reproducible()
+    {
+        let the_move = search_with_seed( 42 ).unwrap();
+        assert!( OthelloSituation::new().copy_apply( the_move.clone() ).is_some() );
+        assert_eq!( search_with_seed( 42 ), Some( the_move ) );
+    }
+
+    #[test]
+    fn tree_is_reused()
+    {
+        let config = MctsConfig{ seed: Some( 1 ), ..MctsConfig::iterations( 300 ) };
+        let mut mcts = Mcts::new( config, RandomPlayout{} );
+        let situation = OthelloSituation::new();
+        let own_move = mcts.search( &situation, None ).unwrap();
+        let situation = situation.copy_apply( own_move ).unwrap();
+        let reply = situation.get_moves().next().unwrap();
+        let situation = situation.copy_apply( reply.clone() ).unwrap();
+
+        mcts.search( &situation, Some( reply ) ).unwrap();
+        assert!( mcts.root_visits() > 300 );
+
+        //Unknown history throws the tree away
+        mcts.search( &situation, None ).unwrap();
+        assert_eq!( mcts.root_visits(), 300 );
+    }
+
+    #[test]
+    fn finished_game_has_no_move()
+    {
+        let mut situation = OthelloSituation::new();
+        while !situation.is_finished()
+        {
+            let the_move = situation.get_moves().next().unwrap();
+            situation = situation.copy_apply( the_move ).unwrap();
+        }
+        let mut mcts = Mcts::new( MctsConfig::iterations( 10 ), RandomPlayout{} );
+        assert_eq!( mcts.search( &situation, None ), None );
+    }
+
+    #[test]
+    fn search_without_limits_stops()
+    {
+        let mut situation = OthelloSituation::new();
+        for _ in 0..50
+        {
+            let the_move = situation.get_moves().next().unwrap();
+            situation = situation.copy_apply( the_move ).unwrap();
+        }
+        let config = MctsConfig{ iterations: None, time: None, seed: Some( 3 ), ..Default::default() };
+        let mut mcts = Mcts::new( config, RandomPlayout{} );
+        assert!( mcts.search( &situation, None ).is_some() );
+        assert_eq!( mcts.root_visits() as u64, DEFAULT_ITERATIONS );
+    }
+}
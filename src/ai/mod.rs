@@ -0,0 +1,568 @@
+pub mod transposition;
+pub mod control;
+pub mod random;
+pub mod mcts;
+pub mod parallel;
+
+use std::mem;
+use game::{GameSituation, ReversibleSituation};
+
+pub use self::transposition::{Bound, TranspositionEntry, TranspositionTable};
+pub use self::control::{SearchLimits, SearchControl};
+pub use self::random::Random;
+pub use self::mcts::{Mcts, MctsConfig, PlayoutPolicy, RandomPlayout};
+pub use self::parallel::{search_root_split, search_iterative_split};
+
+pub trait SituationEvaluator
+{
+    type Situation; 
+    const MAX_SCORE: i32;
+    //Returns an evaluation of situation in the range [-MAX_SCORE, MAX_SCORE]
+    //From the perspective of the current player
+    fn evaluate_situation( &self, situation: &Self::Situation ) -> i32;
+}
+
+pub trait MinMaxTraits
+{
+    type Move: Clone;        
+    type Situation: GameSituation<Move = Self::Move>;
+    type Evaluator: SituationEvaluator<Situation = Self::Situation>;
+}
+
+pub struct MinMax< Traits: MinMaxTraits >
+{
+    evaluator: Traits::Evaluator
+}
+
+impl<Traits: MinMaxTraits> Default for MinMax<Traits> where Traits::Evaluator: Default
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<Traits: MinMaxTraits> MinMax<Traits> where Traits::Evaluator: Default
+{
+    pub fn new() -> Self
+    {
+        Self::with_evaluator( Traits::Evaluator::default() )
+    }
+}
+
+impl<Traits: MinMaxTraits>  MinMax<Traits> 
+{
+    pub fn with_evaluator( evaluator: Traits::Evaluator ) -> Self
+    {
+        MinMax{ evaluator }
+    }
+
+    fn search( &self, situation: Traits::Situation, depth: u32 ) -> i32
+    {
+        if depth == 0 
+        {
+            return self.evaluator.evaluate_situation( &situation );
+        }
+        situation.get_moves()
+                .map( |a_move| situation.copy_apply( a_move ) )
+                .map( |child_situation| -Self::search(self, child_situation.unwrap(), depth - 1) )
+                .max().unwrap_or( self.evaluator.evaluate_situation( &situation ) )
+    }
+
+    pub fn search_root( &self, situation: &Traits::Situation, depth: u32 ) -> ( Option<Traits::Move>, i32 )
+    {
+        if situation.is_finished()
+        {
+            return ( None, self.evaluator.evaluate_situation(situation) );
+        }
+        let mut best_move:Option<Traits::Move> = None;
+        let mut best_score = -Traits::Evaluator::MAX_SCORE;
+        for a_move in situation.get_moves()
+        {
+            let child_score = -Self::search( self, situation.copy_apply( a_move.clone() ).unwrap(), depth - 1 ); 
+            if  child_score >= best_score
+            {
+                best_score = child_score;
+                best_move = Some( a_move );
+            }
+        }
+        ( best_move, best_score )
+    }
+}
+
+impl<Traits: MinMaxTraits> MinMax<Traits> where Traits::Situation: ReversibleSituation + Clone
+{
+    fn search_in_place( &self, situation: &mut Traits::Situation, depth: u32 ) -> i32
+    {
+        if depth == 0 || situation.is_finished()
+        {
+            return self.evaluator.evaluate_situation( situation );
+        }
+        let mut max_value = -Traits::Evaluator::MAX_SCORE;
+        for a_move in situation.get_moves()
+        {
+            let undo = situation.apply( a_move ).unwrap();
+            max_value = max_value.max( -self.search_in_place( situation, depth - 1 ) );
+            situation.undo( undo );
+        }
+        max_value
+    }
+
+    //As search_root, but walks the tree with apply and undo instead of copying
+    pub fn search_root_in_place( &self, situation: &Traits::Situation, depth: u32 ) -> ( Option<Traits::Move>, i32 )
+    {
+        if situation.is_finished()
+        {
+            return ( None, self.evaluator.evaluate_situation(situation) );
+        }
+        let mut situation = situation.clone();
+        let mut best_move:Option<Traits::Move> = None;
+        let mut best_score = -Traits::Evaluator::MAX_SCORE;
+        for a_move in situation.get_moves()
+        {
+            let undo = situation.apply( a_move.clone() ).unwrap();
+            let child_score = -self.search_in_place( &mut situation, depth - 1 );
+            situation.undo( undo );
+            if  child_score >= best_score
+            {
+                best_score = child_score;
+                best_move = Some( a_move );
+            }
+        }
+        ( best_move, best_score )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchVariant
+{
+    AlphaBeta,
+    PrincipalVariation
+}
+
+//How a search gets from a situation to its child and back: enter replaces
+//the situation with the child, leave puts the parent back
+pub trait TreeWalk<Situation: GameSituation>
+{
+    type Token;
+
+    fn enter( situation: &mut Situation, a_move: Situation::Move ) -> Self::Token;
+    fn leave( situation: &mut Situation, token: Self::Token );
+}
+
+//Makes every child with copy_apply and keeps the parent aside meanwhile
+pub struct Copying;
+
+impl<Situation: GameSituation> TreeWalk<Situation> for Copying
+{
+    type Token = Situation;
+
+    fn enter( situation: &mut Situation, a_move: Situation::Move ) -> Situation
+    {
+        let child = situation.copy_apply( a_move ).unwrap();
+        mem::replace( situation, child )
+    }
+
+    fn leave( situation: &mut Situation, parent: Situation )
+    {
+        *situation = parent;
+    }
+}
+
+//Applies the moves to the one situation and undoes them again
+pub struct InPlace;
+
+impl<Situation: ReversibleSituation> TreeWalk<Situation> for InPlace
+{
+    type Token = Situation::Undo;
+
+    fn enter( situation: &mut Situation, a_move: Situation::Move ) -> Situation::Undo
+    {
+        situation.apply( a_move ).unwrap()
+    }
+
+    fn leave( situation: &mut Situation, undo: Situation::Undo )
+    {
+        situation.undo( undo );
+    }
+}
+
+pub trait AlphaBeta
+{
+    type Move: Clone + PartialEq;        
+    type Situation: GameSituation<Move = Self::Move> + Clone;
+    type Evaluator: SituationEvaluator<Situation = Self::Situation>;
+
+    fn evaluator( &self ) -> &Self::Evaluator;
+
+    //Implementors owning a transposition table should return it here
+    fn transposition_table( &mut self ) -> Option<&mut TranspositionTable<Self::Move>>
+    {
+        None
+    }
+
+    //Implementors returning a search control get time and node limits
+    //enforced, others can only be limited by depth
+    fn search_control( &mut self ) -> Option<&mut SearchControl>
+    {
+        None
+    }
+
+    //Once this returns true the results of the current search are garbage
+    fn search_stopped( &mut self ) -> bool
+    {
+        self.search_control().is_some_and( |control| control.is_stopped() )
+    }
+
+    fn search_variant( &self ) -> SearchVariant
+    {
+        SearchVariant::AlphaBeta
+    }
+
+    //Half width of the aspiration window used at the root by the principal
+    //variation search
+    fn aspiration_window( &self ) -> i32
+    {
+        ( Self::Evaluator::MAX_SCORE / 16 ).max( 1 )
+    }
+
+    fn search( &mut self, situation: Self::Situation, depth: u32, alpha: i32, beta: i32 ) -> i32
+    {
+        let mut situation = situation;
+        self.search_tree::<Copying>( &mut situation, depth, alpha, beta, SearchVariant::AlphaBeta )
+    }
+
+    //As search, but walks the tree with apply and undo instead of copying
+    //the situation for every node. The situation is left as it was
+    fn search_in_place( &mut self, situation: &mut Self::Situation, depth: u32, alpha: i32, beta: i32 ) -> i32
+        where Self::Situation: ReversibleSituation
+    {
+        self.search_tree::<InPlace>( situation, depth, alpha, beta, SearchVariant::AlphaBeta )
+    }
+
+    //Principal variation search (NegaScout): the first move is searched with
+    //the full window and the rest with a null window around alpha, which is
+    //enough to prove them worse. A move that fails high is re-searched with
+    //the full window. Returns the same values as search.
+    fn search_pv( &mut self, situation: Self::Situation, depth: u32, alpha: i32, beta: i32 ) -> i32
+    {
+        let mut situation = situation;
+        self.search_tree::<Copying>( &mut situation, depth, alpha, beta, SearchVariant::PrincipalVariation )
+    }
+
+    //As search_pv, but in place like search_in_place
+    fn search_pv_in_place( &mut self, situation: &mut Self::Situation, depth: u32, alpha: i32, beta: i32 ) -> i32
+        where Self::Situation: ReversibleSituation
+    {
+        self.search_tree::<InPlace>( situation, depth, alpha, beta, SearchVariant::PrincipalVariation )
+    }
+
+    //The body of all the searches above: Walk decides how the children are
+    //reached, variant how they are searched. The situation is left as it was
+    fn search_tree<Walk: TreeWalk<Self::Situation>>( &mut self, situation: &mut Self::Situation, depth: u32, mut alpha: i32, beta: i32, variant: SearchVariant ) -> i32
+    {
+        if self.search_control().is_some_and( |control| control.count_node() )
+        {
+            return 0;
+        }
+        if depth == 0 || situation.is_finished()
+        {
+            return self.evaluator().evaluate_situation( situation );
+        }
+
+        let key = situation.hash_key();
+        let mut moves = situation.get_moves().collect::<Vec<_>>();
+        if let Some( score ) = probe_table( self.transposition_table(), key, depth, alpha, beta, &mut moves )
+        {
+            return score;
+        }
+
+        let original_alpha = alpha;
+        let mut max_value = -Self::Evaluator::MAX_SCORE;
+        let mut best_move: Option<Self::Move> = None;
+        for a_move in moves
+        {
+            let token = Walk::enter( situation, a_move.clone() );
+            let value = if variant == SearchVariant::AlphaBeta || best_move.is_none()
+            {
+                -self.search_tree::<Walk>( situation, depth - 1, -beta, -alpha, variant )
+            }
+            else
+            {
+                let value = -self.search_tree::<Walk>( situation, depth - 1, -alpha - 1, -alpha, variant );
+                if value > alpha && value < beta
+                {
+                    -self.search_tree::<Walk>( situation, depth - 1, -beta, -value, variant )
+                }
+                else
+                {
+                    value
+                }
+            };
+            Walk::leave( situation, token );
+            if value > max_value || best_move.is_none()
+            {
+                max_value = value;
+                best_move = Some( a_move );
+            }
+            if value > alpha
+            {
+                alpha = value;
+            }
+            if alpha >= beta
+            {
+                break;
+            }
+        }
+
+        if self.search_stopped()
+        {
+            return 0;
+        }
+        store_result( self.transposition_table(), key, depth, max_value, original_alpha, beta, best_move );
+        max_value
+    }
+
+    //Score of the position after a_move for the side to move there, searched
+    //with the variant of the search. Used for the moves at the root, which
+    //is left as it was. Copies the situation, implementors with reversible
+    //situations should return search_child_in_place instead
+    fn search_child( &mut self, situation: &mut Self::Situation, a_move: &Self::Move, depth: u32, alpha: i32, beta: i32 ) -> i32
+    {
+        self.search_child_with::<Copying>( situation, a_move, depth, alpha, beta )
+    }
+
+    fn search_child_in_place( &mut self, situation: &mut Self::Situation, a_move: &Self::Move, depth: u32, alpha: i32, beta: i32 ) -> i32
+        where Self::Situation: ReversibleSituation
+    {
+        self.search_child_with::<InPlace>( situation, a_move, depth, alpha, beta )
+    }
+
+    fn search_child_with<Walk: TreeWalk<Self::Situation>>( &mut self, situation: &mut Self::Situation, a_move: &Self::Move, depth: u32, alpha: i32, beta: i32 ) -> i32
+    {
+        let variant = self.search_variant();
+        let token = Walk::enter( situation, a_move.clone() );
+        let score = self.search_tree::<Walk>( situation, depth, alpha, beta, variant );
+        Walk::leave( situation, token );
+        score
+    }
+
+    fn search_root( &mut self, situation: &Self::Situation, depth: u32 ) -> ( Option<Self::Move>, i32 )
+    {
+        if situation.is_finished()
+        {
+            return ( None, self.evaluator().evaluate_situation(situation) );
+        }
+        if let Some( control ) = self.search_control()
+        {
+            control.start( SearchLimits::depth( depth ) );
+        }
+        if let Some( table ) = self.transposition_table()
+        {
+            table.new_search();
+        }
+        let hash_move = self.transposition_table()
+            .and_then( |table| table.probe( situation.hash_key() ) )
+            .and_then( |entry| entry.best_move.clone() );
+
+        let mut root = situation.clone();
+        let mut sorted_moves = situation.get_moves()
+            .map( |a_move| (a_move.clone(), -self.search_child( &mut root,
+                                        &a_move,
+                                        2, 
+                                        -Self::Evaluator::MAX_SCORE, 
+                                        Self::Evaluator::MAX_SCORE )))
+            .collect::<Vec<_>>();
+        sorted_moves.sort_by_key( |&(_, score)| -score );
+        let mut sorted_moves = sorted_moves.into_iter().map( |(a_move, _)| a_move ).collect::<Vec<_>>();
+        move_to_front( &mut sorted_moves, hash_move );
+
+        self.search_root_moves( &mut root, &sorted_moves, depth )
+    }
+
+    //Searches the given root moves in order. If the search gets stopped the
+    //result is incomplete and should be discarded. The situation is left as
+    //it was
+    fn search_root_moves( &mut self, situation: &mut Self::Situation, moves: &[Self::Move], depth: u32 ) -> ( Option<Self::Move>, i32 )
+    {
+        self.search_root_window( situation, moves, depth, -Self::Evaluator::MAX_SCORE, Self::Evaluator::MAX_SCORE )
+    }
+
+    //As search_root_moves, but with the window ( alpha, beta ). If the score
+    //is outside the window it is only a bound, like in search
+    fn search_root_window( &mut self, situation: &mut Self::Situation, moves: &[Self::Move], depth: u32, mut alpha: i32, beta: i32 ) -> ( Option<Self::Move>, i32 )
+    {
+        let original_alpha = alpha;
+        let principal_variation = self.search_variant() == SearchVariant::PrincipalVariation;
+        let mut best_move:Option<Self::Move> = None;
+        let mut best_score = -Self::Evaluator::MAX_SCORE;
+        for a_move in moves
+        {
+            let child_score = if !principal_variation || best_move.is_none()
+            {
+                -self.search_child( situation, a_move, depth - 1, -beta, -alpha )
+            }
+            else
+            {
+                let child_score = -self.search_child( situation, a_move, depth - 1, -alpha - 1, -alpha );
+                if child_score > alpha && child_score < beta
+                {
+                    -self.search_child( situation, a_move, depth - 1, -beta, -child_score )
+                }
+                else
+                {
+                    child_score
+                }
+            };
+            if self.search_stopped()
+            {
+                break;
+            }
+            if child_score > best_score || best_move.is_none()
+            {
+                best_score = child_score;
+                best_move = Some( a_move.clone() );
+            }
+            if child_score > alpha
+            {
+                alpha = child_score;
+            }
+            if alpha >= beta
+            {
+                break;
+            }
+        }
+        if !self.search_stopped()
+        {
+            store_result( self.transposition_table(), situation.hash_key(), depth, best_score, original_alpha, beta, best_move.clone() );
+        }
+        ( best_move, best_score )
+    }
+
+    //Aspiration windows: the root is first searched with a narrow window
+    //around the score of the previous iteration. If the true score is
+    //outside of it, the root gets searched again with the full window.
+    fn search_root_aspiration( &mut self, situation: &mut Self::Situation, moves: &[Self::Move], depth: u32, previous_score: i32 ) -> ( Option<Self::Move>, i32 )
+    {
+        let alpha = ( previous_score - self.aspiration_window() ).max( -Self::Evaluator::MAX_SCORE );
+        let beta = ( previous_score + self.aspiration_window() ).min( Self::Evaluator::MAX_SCORE );
+        let result = self.search_root_window( situation, moves, depth, alpha, beta );
+        let failed_low = result.1 <= alpha && alpha > -Self::Evaluator::MAX_SCORE;
+        let failed_high = result.1 >= beta && beta < Self::Evaluator::MAX_SCORE;
+        if ( failed_low || failed_high ) && !self.search_stopped()
+        {
+            return self.search_root_moves( situation, moves, depth );
+        }
+        result
+    }
+
+    //Iterative deepening: searches with depth 1, 2, 3... until the limits
+    //run out and returns the result of the last completed iteration. The
+    //best move of each iteration is searched first in the next one, and the
+    //transposition table (if any) carries the rest of the ordering over.
+    fn search_iterative( &mut self, situation: &Self::Situation, limits: SearchLimits ) -> ( Option<Self::Move>, i32 )
+    {
+        if situation.is_finished()
+        {
+            return ( None, self.evaluator().evaluate_situation(situation) );
+        }
+        if let Some( control ) = self.search_control()
+        {
+            control.start( limits );
+        }
+        if let Some( table ) = self.transposition_table()
+        {
+            table.new_search();
+        }
+
+        let mut root = situation.clone();
+        let mut moves = situation.get_moves().collect::<Vec<_>>();
+        let mut result = ( moves.first().cloned(), -Self::Evaluator::MAX_SCORE );
+        let max_depth = limits.max_depth.unwrap_or( u32::MAX );
+        let mut depth = 1;
+        while depth <= max_depth
+        {
+            let iteration_result = if self.search_variant() == SearchVariant::PrincipalVariation && depth > 1
+            {
+                self.search_root_aspiration( &mut root, &moves, depth, result.1 )
+            }
+            else
+            {
+                self.search_root_moves( &mut root, &moves, depth )
+            };
+            if self.search_stopped()
+            {
+                break;
+            }
+            move_to_front( &mut moves, iteration_result.0.clone() );
+            result = iteration_result;
+            //No point in searching deeper once the game is decided
+            if result.1.abs() >= Self::Evaluator::MAX_SCORE
+            {
+                break;
+            }
+            depth += 1;
+            match self.search_control()
+            {
+                Some( control ) => if !control.can_deepen( depth ) { break },
+                //Without a search control only the depth limit can end the search
+                None => if limits.max_depth.is_none() { break }
+            }
+        }
+        result
+    }
+}
+
+//Looks the position up in the transposition table. Returns the score if the
+//stored result is deep enough to answer the search, otherwise moves the
+//stored best move to the front of the move list.
+fn probe_table<Move: Clone + PartialEq>( table: Option<&mut TranspositionTable<Move>>, key: u64, depth: u32, alpha: i32, beta: i32, moves: &mut [Move] ) -> Option<i32>
+{
+    let entry = table?.probe( key )?;
+    if entry.depth >= depth
+    {
+        match entry.bound
+        {
+            Bound::Exact => return Some( entry.score ),
+            Bound::Lower if entry.score >= beta => return Some( entry.score ),
+            Bound::Upper if entry.score <= alpha => return Some( entry.score ),
+            _ => ()
+        }
+    }
+    move_to_front( moves, entry.best_move.clone() );
+    None
+}
+
+//Stores a search result with the bound implied by the window it was searched with
+fn store_result<Move: Clone>( table: Option<&mut TranspositionTable<Move>>, key: u64, depth: u32, score: i32, alpha: i32, beta: i32, best_move: Option<Move> )
+{
+    if let Some( table ) = table
+    {
+        let ( bound, best_move ) = if score <= alpha
+        {
+            ( Bound::Upper, None )
+        }
+        else if score >= beta
+        {
+            ( Bound::Lower, best_move )
+        }
+        else
+        {
+            ( Bound::Exact, best_move )
+        };
+        table.store( key, depth, score, bound, best_move );
+    }
+}
+
+//Moves the given move (if present) to the front of the list, keeping the
+//order of the rest
+fn move_to_front<Move: PartialEq>( moves: &mut [Move], first: Option<Move> )
+{
+    if let Some( first ) = first
+    {
+        if let Some( position ) = moves.iter().position( |a_move| a_move == &first )
+        {
+            moves[..=position].rotate_right( 1 );
+        }
+    }
+}
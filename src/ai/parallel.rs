@@ -0,0 +1,132 @@
+//Root splitting: the moves at the root are shared out between several
+//searches, each running in its own thread with its own transposition table.
+//The only thing the threads share is the best score found so far, which the
+//later moves are searched against.
+
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::thread;
+use game::GameSituation;
+use super::{AlphaBeta, SearchLimits, SearchVariant, SituationEvaluator, move_to_front};
+
+//Searches one root move with the window ( alpha, MAX_SCORE )
+fn search_move<Search: AlphaBeta>( search: &mut Search, situation: &mut Search::Situation, a_move: &Search::Move, depth: u32, alpha: i32 ) -> i32
+{
+    let beta = Search::Evaluator::MAX_SCORE;
+    if search.search_variant() == SearchVariant::AlphaBeta || alpha == -beta
+    {
+        return -search.search_child( situation, a_move, depth - 1, -beta, -alpha );
+    }
+    let score = -search.search_child( situation, a_move, depth - 1, -alpha - 1, -alpha );
+    if score > alpha
+    {
+        -search.search_child( situation, a_move, depth - 1, -beta, -score )
+    }
+    else
+    {
+        score
+    }
+}
+
+//Searches the root moves with one thread per search. The first move is
+//searched alone so that the others get a useful alpha. The scores equal to
+//the best one are exact, so ties go to the earlier move whatever the timing
+//of the threads. If any of the searches gets stopped the result is incomplete
+//and should be discarded
+pub fn search_root_split<Search>( searches: &mut [&mut Search], situation: &Search::Situation, moves: &[Search::Move], depth: u32 ) -> ( Option<Search::Move>, i32 )
+    where Search: AlphaBeta + Send, Search::Situation: Sync, Search::Move: Send + Sync
+{
+    let max_score = Search::Evaluator::MAX_SCORE;
+    if moves.is_empty()
+    {
+        return ( None, -max_score );
+    }
+    let first_score = search_move( &mut *searches[0], &mut situation.clone(), &moves[0], depth, -max_score );
+    let best_score = AtomicI32::new( first_score );
+    let next_move = AtomicUsize::new( 1 );
+    let scores = thread::scope( |scope|
+    {
+        let workers = searches.iter_mut().map( |search|
+        {
+            let ( best_score, next_move ) = ( &best_score, &next_move );
+            scope.spawn( move ||
+            {
+                let mut root = situation.clone();
+                let mut scores = Vec::new();
+                while !search.search_stopped()
+                {
+                    let index = next_move.fetch_add( 1, Ordering::Relaxed );
+                    if index >= moves.len()
+                    {
+                        break;
+                    }
+                    //One below the best score, so that ties get exact scores too
+                    let alpha = ( best_score.load( Ordering::Relaxed ) - 1 ).max( -max_score );
+                    let score = search_move( &mut **search, &mut root, &moves[index], depth, alpha );
+                    best_score.fetch_max( score, Ordering::Relaxed );
+                    scores.push( ( index, score ) );
+                }
+                scores
+            })
+        }).collect::<Vec<_>>();
+        workers.into_iter().flat_map( |worker| worker.join().unwrap() ).collect::<Vec<_>>()
+    });
+
+    let mut best = ( 0, first_score );
+    for ( index, score ) in scores
+    {
+        if score > best.1 || ( score == best.1 && index < best.0 )
+        {
+            best = ( index, score );
+        }
+    }
+    ( Some( moves[best.0].clone() ), best.1 )
+}
+
+//Iterative deepening over search_root_split. The first search decides when
+//to deepen; the limits apply to each search separately, so a node limit is
+//per thread
+pub fn search_iterative_split<Search>( searches: &mut [&mut Search], situation: &Search::Situation, limits: SearchLimits ) -> ( Option<Search::Move>, i32 )
+    where Search: AlphaBeta + Send, Search::Situation: Sync, Search::Move: Send + Sync
+{
+    if situation.is_finished()
+    {
+        return ( None, searches[0].evaluator().evaluate_situation( situation ) );
+    }
+    for search in searches.iter_mut()
+    {
+        if let Some( control ) = search.search_control()
+        {
+            control.start( limits );
+        }
+        if let Some( table ) = search.transposition_table()
+        {
+            table.new_search();
+        }
+    }
+
+    let mut moves = situation.get_moves().collect::<Vec<_>>();
+    let mut result = ( moves.first().cloned(), -Search::Evaluator::MAX_SCORE );
+    let max_depth = limits.max_depth.unwrap_or( u32::MAX );
+    let mut depth = 1;
+    while depth <= max_depth
+    {
+        let iteration_result = search_root_split( searches, situation, &moves, depth );
+        if searches.iter_mut().any( |search| search.search_stopped() )
+        {
+            break;
+        }
+        move_to_front( &mut moves, iteration_result.0.clone() );
+        result = iteration_result;
+        if result.1.abs() >= Search::Evaluator::MAX_SCORE
+        {
+            break;
+        }
+        depth += 1;
+        match searches[0].search_control()
+        {
+            Some( control ) => if !control.can_deepen( depth ) { break },
+            None => if limits.max_depth.is_none() { break }
+        }
+    }
+    result
+}
@@ -0,0 +1,41 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//Small xorshift64* generator, good enough for playouts and move variety.
+//Seeded explicitly so that searches can be made reproducible
+#[derive(Clone, Debug)]
+pub struct Random(u64);
+
+impl Random
+{
+    pub fn new( seed: u64 ) -> Random
+    {
+        //Zero is a fixed point of xorshift
+        Random( if seed == 0 { 0x9e3779b97f4a7c15 } else { seed } )
+    }
+
+    pub fn from_clock() -> Random
+    {
+        let nanos = SystemTime::now().duration_since( UNIX_EPOCH ).map( |time| time.as_nanos() as u64 ).unwrap_or( 0 );
+        Random::new( nanos )
+    }
+
+    pub fn next_u64( &mut self ) -> u64
+    {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul( 0x2545f4914f6cdd1d )
+    }
+
+    //Uniform in [0, 1)
+    pub fn next_f64( &mut self ) -> f64
+    {
+        ( self.next_u64() >> 11 ) as f64 / ( 1u64 << 53 ) as f64
+    }
+
+    //Uniform in [0, bound), bound must not be zero
+    pub fn below( &mut self, bound: usize ) -> usize
+    {
+        ( self.next_u64() % bound as u64 ) as usize
+    }
+}
@@ -0,0 +1,155 @@
+//Fixed size transposition table for the alpha-beta family of searches.
+//The table is indexed with the low bits of GameSituation::hash_key and
+//stores the whole key so that index collisions can be detected.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound
+{
+    Exact, //The score is the true minimax value of the position
+    Lower, //The search failed high, true value is at least the score
+    Upper  //The search failed low, true value is at most the score
+}
+
+#[derive(Clone, Debug)]
+pub struct TranspositionEntry<Move>
+{
+    pub key: u64,
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+    generation: u8
+}
+
+pub struct TranspositionTable<Move>
+{
+    entries: Vec<Option<TranspositionEntry<Move>>>,
+    generation: u8
+}
+
+impl<Move: Clone> TranspositionTable<Move>
+{
+    //The size is rounded down to the nearest power of two
+    pub fn new( size: usize ) -> Self
+    {
+        let size = if size <= 1 { 1 } else { 1 << ( usize::BITS - 1 - size.leading_zeros() ) };
+        TranspositionTable{ entries: vec![None; size], generation: 0 }
+    }
+
+    pub fn size( &self ) -> usize
+    {
+        self.entries.len()
+    }
+
+    fn index( &self, key: u64 ) -> usize
+    {
+        ( key as usize ) & ( self.entries.len() - 1 )
+    }
+
+    pub fn clear( &mut self )
+    {
+        for entry in self.entries.iter_mut()
+        {
+            *entry = None;
+        }
+        self.generation = 0;
+    }
+
+    //Should be called once per root search, so that entries left over from
+    //earlier searches get replaced first
+    pub fn new_search( &mut self )
+    {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe( &self, key: u64 ) -> Option<&TranspositionEntry<Move>>
+    {
+        match self.entries[ self.index(key) ]
+        {
+            Some( ref entry ) if entry.key == key => Some( entry ),
+            _ => None
+        }
+    }
+
+    //Replacement policy: empty slots and entries from earlier searches are
+    //always replaced. Otherwise deeper results are preferred over shallower
+    //ones, but a result for the same position always wins so that bounds
+    //don't go stale.
+    pub fn store( &mut self, key: u64, depth: u32, score: i32, bound: Bound, best_move: Option<Move> )
+    {
+        let index = self.index(key);
+        let generation = self.generation;
+        let replace = match self.entries[index]
+        {
+            None => true,
+            Some( ref old ) => old.key == key || old.generation != generation || depth >= old.depth
+        };
+        if !replace
+        {
+            return;
+        }
+        //Keep the old best move if the new search didn't produce one
+        let best_move = match ( best_move, &self.entries[index] )
+        {
+            ( None, Some( old ) ) if old.key == key => old.best_move.clone(),
+            ( best_move, _ ) => best_move
+        };
+        self.entries[index] = Some( TranspositionEntry{ key, depth, score, bound, best_move, generation } );
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn size_is_power_of_two()
+    {
+        assert_eq!( TranspositionTable::<u8>::new( 1024 ).size(), 1024 );
+        assert_eq!( TranspositionTable::<u8>::new( 1000 ).size(), 512 );
+        assert_eq!( TranspositionTable::<u8>::new( 0 ).size(), 1 );
+    }
+
+    #[test]
+    fn store_and_probe()
+    {
+        let mut table = TranspositionTable::new( 16 );
+        table.store( 0x1234, 3, 10, Bound::Lower, Some( 7u8 ) );
+        let entry = table.probe( 0x1234 ).unwrap();
+        assert_eq!( entry.depth, 3 );
+        assert_eq!( entry.score, 10 );
+        assert_eq!( entry.bound, Bound::Lower );
+        assert_eq!( entry.best_move, Some( 7 ) );
+        //Same index, different key
+        assert!( table.probe( 0x1234 + 16 ).is_none() );
+    }
+
+    #[test]
+    fn replacement_prefers_depth_within_a_search()
+    {
+        let mut table = TranspositionTable::new( 16 );
+        table.store( 1, 5, 1, Bound::Exact, Some( 1u8 ) );
+        table.store( 17, 2, 2, Bound::Exact, Some( 2u8 ) );
+        assert!( table.probe( 1 ).is_some() );
+        assert!( table.probe( 17 ).is_none() );
+
+        table.new_search();
+        table.store( 17, 2, 2, Bound::Exact, Some( 2u8 ) );
+        assert!( table.probe( 1 ).is_none() );
+        assert_eq!( table.probe( 17 ).unwrap().score, 2 );
+    }
+
+    #[test]
+    fn same_position_keeps_best_move()
+    {
+        let mut table = TranspositionTable::new( 16 );
+        table.store( 3, 5, 1, Bound::Lower, Some( 4u8 ) );
+        table.store( 3, 1, -2, Bound::Upper, None );
+        let entry = table.probe( 3 ).unwrap();
+        assert_eq!( entry.depth, 1 );
+        assert_eq!( entry.best_move, Some( 4 ) );
+    }
+}
@@ -0,0 +1,63 @@
+//A stand-in for third party engines in the tests of the external players.
+//  stub_engine gtp       plays the first legal move over the gtp protocol
+//  stub_engine nboard    searches to depth 1 over the NBoard protocol
+//  stub_engine crash | garbage | illegal | slow
+//                        answers gtp commands but misbehaves on genmove
+extern crate bit_board;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::thread;
+use std::time::Duration;
+use bit_board::gtp::GtpEngine;
+use bit_board::othello::OthelloSituation;
+use bit_board::othello::nboard::NBoardEngine;
+use bit_board::othello::players::{DummyOthelloPlayer, OthelloAlphaBetaPlayer};
+
+fn misbehave( mode: &str ) -> io::Result<()>
+{
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines()
+    {
+        let line = line?;
+        let id = line.split_whitespace().next().filter(|word| word.parse::<u32>().is_ok()).unwrap_or("");
+        if !line.contains("genmove")
+        {
+            write!(stdout, "={} \n\n", id)?;
+        }
+        else
+        {
+            match mode
+            {
+                "crash" => process::exit(3),
+                "garbage" => write!(stdout, "I am thinking about it\n\n")?,
+                "illegal" => write!(stdout, "={} A1\n\n", id)?,
+                _ =>
+                {
+                    thread::sleep(Duration::from_secs(10));
+                    write!(stdout, "={} PASS\n\n", id)?;
+                }
+            }
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn main()
+{
+    let mode = env::args().nth(1).unwrap_or_default();
+    let stdin = io::stdin();
+    let result = match mode.as_str()
+    {
+        "gtp" => GtpEngine::new(OthelloSituation::new(), 8, DummyOthelloPlayer::new()).run(stdin.lock(), io::stdout()),
+        "nboard" => NBoardEngine::with_player(OthelloAlphaBetaPlayer::new(1), 1).run(stdin.lock(), io::stdout()),
+        _ => misbehave(&mode)
+    };
+    if let Err(error) = result
+    {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
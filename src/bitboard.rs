@@ -1,4 +1,5 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 use std::ops::{BitOr, BitAnd, BitXor, BitOrAssign, BitAndAssign, BitXorAssign, Not};
 
@@ -31,13 +32,14 @@ impl Coord
     {
         self.0
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self>
     {
        	let chars:Vec<char> = s.trim().chars().collect();
         if chars.len() == 2
         {
             let col = "abcdefgh".find(chars[0])?;
-            let row = chars[1].to_digit(10).unwrap_or(0) as u32;
+            let row = chars[1].to_digit(10).unwrap_or(0);
             let row = row.checked_sub(1)?;
             return Coord::new( row, col as u32 );
         }
@@ -45,6 +47,32 @@ impl Coord
     }
 }
 
+impl Coord
+{
+    //The square's name, like a1 or h8
+    pub fn algebraic( &self ) -> String
+    {
+        format!( "{}{}", ( b'a' + self.get_col() as u8 ) as char, self.get_row() + 1 )
+    }
+
+    pub fn transform( &self, symmetry: Symmetry ) -> Coord
+    {
+        let ( row, col ) = ( self.get_row(), self.get_col() );
+        let ( row, col ) = match symmetry
+        {
+            Symmetry::Identity => ( row, col ),
+            Symmetry::FlipVertical => ( 7 - row, col ),
+            Symmetry::FlipHorizontal => ( row, 7 - col ),
+            Symmetry::FlipDiagonal => ( col, row ),
+            Symmetry::FlipAntiDiagonal => ( 7 - col, 7 - row ),
+            Symmetry::Rotate90 => ( col, 7 - row ),
+            Symmetry::Rotate180 => ( 7 - row, 7 - col ),
+            Symmetry::Rotate270 => ( 7 - col, row )
+        };
+        Coord( col + row * 8 )
+    }
+}
+
 impl fmt::Display for Coord {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}, {})", self.get_row(), self.get_col())
@@ -73,6 +101,65 @@ pub enum Direction
     DownRight
 }
 
+impl Direction
+{
+    pub fn all() -> [Direction; 8]
+    {
+        [ Direction::Right, Direction::UpRight, Direction::Up, Direction::UpLeft,
+          Direction::Left, Direction::DownLeft, Direction::Down, Direction::DownRight ]
+    }
+
+    pub fn opposite( &self ) -> Direction
+    {
+        match *self
+        {
+            Direction::Right => Direction::Left,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::Up => Direction::Down,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::Left => Direction::Right,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::Down => Direction::Up,
+            Direction::DownRight => Direction::UpLeft
+        }
+    }
+}
+
+//The eight symmetries of the board. The flips mirror rows (vertical), columns
+//(horizontal), across the a1-h8 diagonal or across the a8-h1 diagonal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry
+{
+    Identity,
+    FlipVertical,
+    FlipHorizontal,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+    Rotate90,
+    Rotate180,
+    Rotate270
+}
+
+impl Symmetry
+{
+    pub fn all() -> [Symmetry; 8]
+    {
+        [ Symmetry::Identity, Symmetry::FlipVertical, Symmetry::FlipHorizontal, Symmetry::FlipDiagonal,
+          Symmetry::FlipAntiDiagonal, Symmetry::Rotate90, Symmetry::Rotate180, Symmetry::Rotate270 ]
+    }
+
+    //The symmetry that undoes this one
+    pub fn inverse( &self ) -> Symmetry
+    {
+        match *self
+        {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BitBoard(u64);
 
@@ -83,6 +170,17 @@ impl BitBoard
         BitBoard(0)
     }
 
+    pub fn from_bits( bits: u64 ) -> BitBoard
+    {
+        BitBoard( bits )
+    }
+
+    //Bit n is the square with index n, see Coord::get_idx
+    pub fn bits( &self ) -> u64
+    {
+        self.0
+    }
+
     pub fn rightmost_column() -> BitBoard
     {
         BitBoard( 0b1000000010000000100000001000000010000000100000001000000010000000u64 )
@@ -158,6 +256,64 @@ impl BitBoard
         }
     }
 
+    pub fn flip_vertical( &self ) -> BitBoard
+    {
+        BitBoard( self.0.swap_bytes() )
+    }
+
+    pub fn flip_horizontal( &self ) -> BitBoard
+    {
+        BitBoard( self.0.reverse_bits().swap_bytes() )
+    }
+
+    //Mirrors across the a1-h8 diagonal by swapping bit blocks of halving size
+    pub fn flip_diagonal( &self ) -> BitBoard
+    {
+        let mut bits = self.0;
+        let mut swap = 0x0f0f_0f0f_0000_0000 & ( bits ^ ( bits << 28 ) );
+        bits ^= swap ^ ( swap >> 28 );
+        swap = 0x3333_0000_3333_0000 & ( bits ^ ( bits << 14 ) );
+        bits ^= swap ^ ( swap >> 14 );
+        swap = 0x5500_5500_5500_5500 & ( bits ^ ( bits << 7 ) );
+        bits ^= swap ^ ( swap >> 7 );
+        BitBoard( bits )
+    }
+
+    pub fn flip_anti_diagonal( &self ) -> BitBoard
+    {
+        self.flip_diagonal().rotate_180()
+    }
+
+    pub fn rotate_90( &self ) -> BitBoard
+    {
+        self.flip_diagonal().flip_horizontal()
+    }
+
+    pub fn rotate_180( &self ) -> BitBoard
+    {
+        BitBoard( self.0.reverse_bits() )
+    }
+
+    pub fn rotate_270( &self ) -> BitBoard
+    {
+        self.flip_diagonal().flip_vertical()
+    }
+
+    pub fn transform( &self, symmetry: Symmetry ) -> BitBoard
+    {
+        match symmetry
+        {
+            Symmetry::Identity => *self,
+            Symmetry::FlipVertical => self.flip_vertical(),
+            Symmetry::FlipHorizontal => self.flip_horizontal(),
+            Symmetry::FlipDiagonal => self.flip_diagonal(),
+            Symmetry::FlipAntiDiagonal => self.flip_anti_diagonal(),
+            Symmetry::Rotate90 => self.rotate_90(),
+            Symmetry::Rotate180 => self.rotate_180(),
+            Symmetry::Rotate270 => self.rotate_270()
+        }
+    }
+
     pub fn first_one(&self) -> Option<Coord>
     {
         if self.is_empty()
@@ -185,6 +341,14 @@ impl PartialEq for BitBoard
     }
 }
 
+impl Hash for BitBoard
+{
+    fn hash<H: Hasher>(&self, state: &mut H)
+    {
+        self.0.hash(state);
+    }
+}
+
 impl BitOr for BitBoard
 {
     type Output = BitBoard;
@@ -253,7 +417,7 @@ impl fmt::Display for BitBoard
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for row in 0..8
         {
-            write!(f, "\n")?;
+            writeln!(f)?;
             for col in 0..8
             {
 
@@ -301,6 +465,7 @@ impl IntoIterator for BitBoard {
 #[cfg(test)]
 mod test
 {
+    use test::Bencher;
     use super::*;
 
     fn test_pattern( ) -> BitBoard
@@ -370,4 +535,53 @@ mod test
         assert_eq!( coord.get_row(), 2 );
         assert_eq!( coord.get_col(), 1 );
     }
-}
\ No newline at end of file
+
+    fn all_coords() -> Vec<Coord>
+    {
+        ( 0..64 ).map( |idx| Coord::new( idx / 8, idx % 8 ).unwrap() ).collect()
+    }
+
+    #[test]
+    fn bitboard_transforms_match_coord_transforms()
+    {
+        for symmetry in Symmetry::all()
+        {
+            for coord in all_coords()
+            {
+                let expected = BitBoard::empty().with_one_at( coord.transform( symmetry ) );
+                assert_eq!( BitBoard::empty().with_one_at( coord ).transform( symmetry ), expected, "{:?} {}", symmetry, coord );
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_transform()
+    {
+        let board = BitBoard::from_bits( 0x0123_4567_89ab_cdef );
+        for symmetry in Symmetry::all()
+        {
+            assert_eq!( board.transform( symmetry ).transform( symmetry.inverse() ), board );
+            for coord in all_coords()
+            {
+                assert_eq!( coord.transform( symmetry ).transform( symmetry.inverse() ), coord );
+            }
+        }
+    }
+
+    #[test]
+    fn transforms_move_corners()
+    {
+        let a1 = Coord::from_str( "a1" ).unwrap();
+        let expected = [ "a1", "a8", "h1", "a1", "h8", "h1", "h8", "a8" ];
+        for ( symmetry, square ) in Symmetry::all().iter().zip( expected.iter() )
+        {
+            assert_eq!( a1.transform( *symmetry ), Coord::from_str( square ).unwrap(), "{:?}", symmetry );
+        }
+    }
+
+    #[bench]
+    fn bench_all_transforms(b: &mut Bencher) {
+        let board = BitBoard::from_bits( 0x0123_4567_89ab_cdef );
+        b.iter(|| Symmetry::all().iter().fold( 0, |bits, &symmetry| bits ^ board.transform( symmetry ).bits() ));
+    }
+}
@@ -0,0 +1,299 @@
+//Players that are other programs, run as child processes and talked to over
+//their standard input and output. The protocol is pluggable: GtpProtocol
+//speaks the protocol of the gtp module, othello::nboard::NBoardProtocol the
+//one of the NBoard GUI. A program that crashes, answers garbage or takes
+//longer than the timeout loses: make_move returns None, which forfeits the
+//game, and error tells why.
+
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use game::{GameSituation, MoveNotation, Player};
+
+#[derive(Debug)]
+pub enum ExternalError
+{
+    Io( io::Error ),
+    //The program closed its output, usually because it exited
+    Exited,
+    Timeout,
+    //Output the protocol does not understand
+    Garbage( String ),
+    Resigned,
+    //The program cannot be brought to the position with the protocol
+    OutOfSync
+}
+
+impl fmt::Display for ExternalError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            ExternalError::Io( ref error ) => write!(f, "{}", error),
+            ExternalError::Exited => write!(f, "the program exited"),
+            ExternalError::Timeout => write!(f, "no move within the time limit"),
+            ExternalError::Garbage( ref line ) => write!(f, "unexpected output '{}'", line),
+            ExternalError::Resigned => write!(f, "the program resigned"),
+            ExternalError::OutOfSync => write!(f, "cannot set up the position")
+        }
+    }
+}
+
+impl error::Error for ExternalError {}
+
+impl From<io::Error> for ExternalError
+{
+    fn from( error: io::Error ) -> ExternalError
+    {
+        match error.kind()
+        {
+            io::ErrorKind::BrokenPipe => ExternalError::Exited,
+            _ => ExternalError::Io( error )
+        }
+    }
+}
+
+//The text protocol between the player and the program
+pub trait EngineProtocol
+{
+    type Situation: GameSituation;
+
+    //The lines that ask for a move in the situation, previous_move being the
+    //move that led to it if any
+    fn request( &mut self, situation: &Self::Situation, previous_move: Option<&<Self::Situation as GameSituation>::Move> ) -> Result<Vec<String>, ExternalError>;
+
+    //Reads a line of the answer: the move once it is there, None for lines
+    //to skip
+    fn parse_reply( &mut self, situation: &Self::Situation, line: &str ) -> Result<Option<<Self::Situation as GameSituation>::Move>, ExternalError>;
+}
+
+pub struct ExternalPlayer<Protocol>
+{
+    child: Child,
+    stdin: ChildStdin,
+    //Lines of the standard output, read by a thread so that reads can time out
+    lines: Receiver<String>,
+    protocol: Protocol,
+    timeout: Duration,
+    error: Option<ExternalError>
+}
+
+impl<Protocol: EngineProtocol> ExternalPlayer<Protocol>
+{
+    //Starts the program; its standard input and output are taken over
+    pub fn new( mut command: Command, protocol: Protocol, timeout: Duration ) -> io::Result<ExternalPlayer<Protocol>>
+    {
+        let mut child = command.stdin( Stdio::piped() ).stdout( Stdio::piped() ).spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new( child.stdout.take().unwrap() );
+        let ( sender, lines ) = mpsc::channel();
+        thread::spawn( move ||
+        {
+            for line in stdout.lines()
+            {
+                match line
+                {
+                    Ok( line ) => if sender.send( line ).is_err() { break },
+                    Err( _ ) => break
+                }
+            }
+        });
+        Ok( ExternalPlayer{ child, stdin, lines, protocol, timeout, error: None } )
+    }
+
+    //Why the program lost, None while it is playing
+    pub fn error( &self ) -> Option<&ExternalError>
+    {
+        self.error.as_ref()
+    }
+
+    fn ask( &mut self, situation: &Protocol::Situation, previous_move: Option<&<Protocol::Situation as GameSituation>::Move> ) -> Result<<Protocol::Situation as GameSituation>::Move, ExternalError>
+    {
+        for line in self.protocol.request( situation, previous_move )?
+        {
+            writeln!( self.stdin, "{}", line )?;
+        }
+        self.stdin.flush()?;
+        let deadline = Instant::now() + self.timeout;
+        loop
+        {
+            let line = match self.lines.recv_timeout( deadline.saturating_duration_since( Instant::now() ) )
+            {
+                Ok( line ) => line,
+                Err( RecvTimeoutError::Timeout ) => return Err( ExternalError::Timeout ),
+                Err( RecvTimeoutError::Disconnected ) => return Err( ExternalError::Exited )
+            };
+            if let Some( the_move ) = self.protocol.parse_reply( situation, &line )?
+            {
+                return Ok( the_move );
+            }
+        }
+    }
+}
+
+impl<Protocol: EngineProtocol> Player for ExternalPlayer<Protocol>
+{
+    type Move = <Protocol::Situation as GameSituation>::Move;
+    type Situation = Protocol::Situation;
+    fn make_move( &mut self, situation: &Self::Situation, previous_move: Option<Self::Move> ) -> Option<Self::Move>
+    {
+        if self.error.is_some()
+        {
+            return None;
+        }
+        match self.ask( situation, previous_move.as_ref() )
+        {
+            Ok( the_move ) => Some( the_move ),
+            Err( error ) =>
+            {
+                self.error = Some( error );
+                let _ = self.child.kill();
+                None
+            }
+        }
+    }
+}
+
+impl<Protocol> Drop for ExternalPlayer<Protocol>
+{
+    fn drop( &mut self )
+    {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//The client side of the gtp module. The program is kept in step with play
+//commands, or cleared when a new game starts
+pub struct GtpProtocol<Situation>
+{
+    start: Situation,
+    //The position the program has, None before the first request
+    position: Option<Situation>,
+    next_id: u32,
+    //Id of the genmove that is waiting for an answer
+    genmove_id: u32
+}
+
+impl<Situation> GtpProtocol<Situation>
+{
+    //start is the position after clear_board
+    pub fn new( start: Situation ) -> GtpProtocol<Situation>
+    {
+        GtpProtocol{ start, position: None, next_id: 1, genmove_id: 0 }
+    }
+
+    fn command( &mut self, command: String ) -> String
+    {
+        self.next_id += 1;
+        format!( "{} {}", self.next_id - 1, command )
+    }
+}
+
+impl<Situation> EngineProtocol for GtpProtocol<Situation>
+    where Situation: MoveNotation + Clone + PartialEq,
+          Situation::Move: Clone
+{
+    type Situation = Situation;
+
+    fn request( &mut self, situation: &Situation, previous_move: Option<&Situation::Move> ) -> Result<Vec<String>, ExternalError>
+    {
+        let mut lines = Vec::new();
+        let after = |position: &Situation| previous_move.and_then( |the_move| position.copy_apply( the_move.clone() ) );
+        let position = self.position.take();
+        if position.as_ref() != Some( situation )
+        {
+            let played_from = match position
+            {
+                Some( ref position ) if after( position ).as_ref() == Some( situation ) => Some( position.clone() ),
+                _ =>
+                {
+                    lines.push( self.command( "clear_board".to_string() ) );
+                    if *situation == self.start { None }
+                    else if after( &self.start ).as_ref() == Some( situation ) { Some( self.start.clone() ) }
+                    else { return Err( ExternalError::OutOfSync ) }
+                }
+            };
+            if let Some( played_from ) = played_from
+            {
+                let role = Situation::format_role( &played_from.get_turn() );
+                let text = played_from.format_move( previous_move.unwrap() );
+                lines.push( self.command( format!( "play {} {}", role, text ) ) );
+            }
+        }
+        self.genmove_id = self.next_id;
+        lines.push( self.command( format!( "genmove {}", Situation::format_role( &situation.get_turn() ) ) ) );
+        Ok( lines )
+    }
+
+    fn parse_reply( &mut self, situation: &Situation, line: &str ) -> Result<Option<Situation::Move>, ExternalError>
+    {
+        let line = line.trim();
+        if line.is_empty()
+        {
+            return Ok( None );
+        }
+        let garbage = || ExternalError::Garbage( line.to_string() );
+        if !line.starts_with( '=' )
+        {
+            //Also ? for a command the program rejected
+            return Err( garbage() );
+        }
+        let mut words = line[1..].splitn( 2, char::is_whitespace );
+        if words.next().and_then( |id| id.parse::<u32>().ok() ) != Some( self.genmove_id )
+        {
+            return Ok( None );
+        }
+        let answer = words.next().unwrap_or( "" ).trim();
+        if answer.eq_ignore_ascii_case( "resign" )
+        {
+            return Err( ExternalError::Resigned );
+        }
+        let the_move = situation.parse_move( answer ).ok_or_else( garbage )?;
+        self.position = situation.copy_apply( the_move.clone() );
+        Ok( Some( the_move ) )
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::str::FromStr;
+    use othello::{OthelloSituation, OthelloMove};
+
+    fn situation_after( moves: &[&str] ) -> OthelloSituation
+    {
+        moves.iter().fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str( a_move ).unwrap() ).unwrap() )
+    }
+
+    #[test]
+    fn gtp_protocol_keeps_the_program_in_step()
+    {
+        let mut protocol = GtpProtocol::new( OthelloSituation::new() );
+        let start = OthelloSituation::new();
+        assert_eq!( protocol.request( &start, None ).unwrap(), vec![ "1 clear_board", "2 genmove B" ] );
+        assert_eq!( protocol.parse_reply( &start, "=1 " ).unwrap(), None );
+        assert_eq!( protocol.parse_reply( &start, "" ).unwrap(), None );
+        assert_eq!( protocol.parse_reply( &start, "=2 F5" ).unwrap(), Some( OthelloMove::from_str( "f5" ).unwrap() ) );
+
+        let f4 = OthelloMove::from_str( "f4" ).unwrap();
+        let situation = situation_after( &[ "f5", "f4" ] );
+        assert_eq!( protocol.request( &situation, Some( &f4 ) ).unwrap(), vec![ "3 play W F4", "4 genmove B" ] );
+        assert!( matches!( protocol.parse_reply( &situation, "=4 resign" ), Err( ExternalError::Resigned ) ) );
+
+        //A new game where the program is white
+        let situation = situation_after( &[ "d3" ] );
+        assert_eq!( protocol.request( &situation, Some( &OthelloMove::from_str( "d3" ).unwrap() ) ).unwrap(), vec![ "5 clear_board", "6 play B D3", "7 genmove W" ] );
+        assert!( matches!( protocol.parse_reply( &situation, "=7 Z9" ), Err( ExternalError::Garbage( _ ) ) ) );
+        assert!( matches!( protocol.parse_reply( &situation, "?6 illegal move" ), Err( ExternalError::Garbage( _ ) ) ) );
+        assert!( matches!( protocol.request( &situation_after( &[ "d3", "c3", "c4" ] ), None ), Err( ExternalError::OutOfSync ) ) );
+    }
+}
@@ -10,6 +10,36 @@ pub trait GameSituation: Sized
     fn get_turn( &self ) -> Self::Role;
     fn is_finished( &self ) -> bool;
     fn get_winner( &self ) -> Option<Self::Role>; 
+
+    //Hash of the whole position including the side to move. Used as the key
+    //for transposition tables, so equal positions must have equal keys
+    fn hash_key( &self ) -> u64;
+}
+
+//In place alternative to copy_apply for searches that walk the tree: apply
+//changes the situation and returns what undo needs to change it back
+pub trait ReversibleSituation: GameSituation
+{
+    type Undo;
+
+    //Returns None and leaves the situation unchanged if the move is illegal
+    fn apply( &mut self, the_move: Self::Move ) -> Option<Self::Undo>;
+    //Takes back the last applied move
+    fn undo( &mut self, undo: Self::Undo );
+}
+
+//Moves and sides as text, for the engine protocols
+pub trait MoveNotation: GameSituation
+{
+    //None if the text is not a move; the move is not checked for legality
+    fn parse_move( &self, text: &str ) -> Option<Self::Move>;
+    fn format_move( &self, the_move: &Self::Move ) -> String;
+
+    fn parse_role( text: &str ) -> Option<Self::Role>;
+    fn format_role( role: &Self::Role ) -> String;
+
+    //The result of a finished game, like B+12, or 0 for a draw
+    fn format_result( &self ) -> String;
 }
 
 pub trait Player 
@@ -17,4 +47,18 @@ pub trait Player
     type Move;       
     type Situation: GameSituation<Move = Self::Move>;
     fn make_move( &mut self, situation: &Self::Situation, previous_move: Option<Self::Move> ) -> Option<Self::Move>;
-}
\ No newline at end of file
+}
+
+//Counts the leaf nodes of the game tree to the given depth, for validating
+//move generation. Passes count as moves, and finished games are counted as
+//leaves wherever they end
+pub fn perft<Situation: GameSituation>( situation: &Situation, depth: u32 ) -> u64
+{
+    if depth == 0 || situation.is_finished()
+    {
+        return 1;
+    }
+    situation.get_moves()
+        .map( |a_move| perft( &situation.copy_apply( a_move ).unwrap(), depth - 1 ) )
+        .sum()
+}
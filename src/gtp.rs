@@ -0,0 +1,235 @@
+//A line based engine protocol modelled on GTP, the Go Text Protocol, for any
+//game with a text notation. Commands may start with a numeric id that is
+//repeated in the answer, and are answered with = result or ? error followed
+//by an empty line. # starts a comment. Supported commands:
+//  protocol_version, name, version, known_command, list_commands, quit,
+//  boardsize n, clear_board, komi, play color move, genmove color, undo,
+//  showboard, final_score
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use game::{MoveNotation, Player};
+
+const COMMANDS: [&str; 14] = [ "protocol_version", "name", "version", "known_command", "list_commands", "quit",
+                               "boardsize", "clear_board", "komi", "play", "genmove", "undo", "showboard", "final_score" ];
+
+pub struct GtpEngine<Situation: MoveNotation, P>
+{
+    start: Situation,
+    //The only size accepted by boardsize
+    size: u32,
+    player: P,
+    //The positions before each move, for undo
+    history: Vec<( Situation, Situation::Move )>,
+    situation: Situation
+}
+
+impl<Situation, P> GtpEngine<Situation, P>
+    where Situation: MoveNotation + Clone + fmt::Display,
+          Situation::Move: Clone,
+          P: Player<Situation = Situation, Move = Situation::Move>
+{
+    pub fn new( start: Situation, size: u32, player: P ) -> GtpEngine<Situation, P>
+    {
+        GtpEngine{ situation: start.clone(), start, size, player, history: Vec::new() }
+    }
+
+    pub fn situation( &self ) -> &Situation
+    {
+        &self.situation
+    }
+
+    //Answers the commands of input until quit or the end of the input
+    pub fn run<R: BufRead, W: Write>( &mut self, input: R, mut output: W ) -> io::Result<()>
+    {
+        for line in input.lines()
+        {
+            let line = line?;
+            let line = line.split( '#' ).next().unwrap().trim();
+            if line.is_empty()
+            {
+                continue;
+            }
+            let mut words = line.split_whitespace().peekable();
+            let id = match words.peek().map( |word| word.parse::<u32>() )
+            {
+                Some( Ok( id ) ) => { words.next(); id.to_string() },
+                _ => String::new()
+            };
+            let command = words.next().unwrap_or( "" );
+            let arguments = words.collect::<Vec<_>>();
+            match self.command( command, &arguments )
+            {
+                Ok( answer ) => write!( output, "={} {}\n\n", id, answer )?,
+                Err( error ) => write!( output, "?{} {}\n\n", id, error )?
+            }
+            output.flush()?;
+            if command == "quit"
+            {
+                break;
+            }
+        }
+        Ok( () )
+    }
+
+    fn command( &mut self, command: &str, arguments: &[&str] ) -> Result<String, String>
+    {
+        match command
+        {
+            "protocol_version" => Ok( "2".to_string() ),
+            "name" => Ok( "bit_board".to_string() ),
+            "version" => Ok( env!( "CARGO_PKG_VERSION" ).to_string() ),
+            "known_command" => Ok( COMMANDS.contains( &argument( arguments, 0 )? ).to_string() ),
+            "list_commands" => Ok( COMMANDS.join( "\n" ) ),
+            "quit" => Ok( String::new() ),
+            "boardsize" => match argument( arguments, 0 )?.parse::<u32>()
+            {
+                Ok( size ) if size == self.size =>
+                {
+                    self.clear();
+                    Ok( String::new() )
+                },
+                Ok( _ ) => Err( "unacceptable size".to_string() ),
+                Err( _ ) => Err( "syntax error".to_string() )
+            },
+            "clear_board" =>
+            {
+                self.clear();
+                Ok( String::new() )
+            },
+            //Only meaningful for Go, accepted for the GUIs that always send it
+            "komi" => Ok( String::new() ),
+            "play" =>
+            {
+                self.check_turn( argument( arguments, 0 )? )?;
+                let the_move = self.situation.parse_move( argument( arguments, 1 )? ).ok_or_else( || "syntax error".to_string() )?;
+                self.play( the_move ).ok_or_else( || "illegal move".to_string() )?;
+                Ok( String::new() )
+            },
+            "genmove" =>
+            {
+                self.check_turn( argument( arguments, 0 )? )?;
+                let previous = self.history.last().map( |( _, the_move )| the_move.clone() );
+                match self.player.make_move( &self.situation, previous )
+                {
+                    None => Ok( "resign".to_string() ),
+                    Some( the_move ) =>
+                    {
+                        let text = self.situation.format_move( &the_move );
+                        self.play( the_move ).ok_or_else( || format!( "engine played the illegal move {}", text ) )?;
+                        Ok( text )
+                    }
+                }
+            },
+            "undo" => match self.history.pop()
+            {
+                Some( ( situation, _ ) ) =>
+                {
+                    self.situation = situation;
+                    Ok( String::new() )
+                },
+                None => Err( "cannot undo".to_string() )
+            },
+            "showboard" => Ok( format!( "\n{}", self.situation ) ),
+            "final_score" if self.situation.is_finished() => Ok( self.situation.format_result() ),
+            "final_score" => Err( "game is not over".to_string() ),
+            _ => Err( "unknown command".to_string() )
+        }
+    }
+
+    fn clear( &mut self )
+    {
+        self.situation = self.start.clone();
+        self.history.clear();
+    }
+
+    //Moves are only accepted for the side to move
+    fn check_turn( &self, color: &str ) -> Result<(), String>
+    {
+        let role = Situation::parse_role( color ).ok_or_else( || "syntax error".to_string() )?;
+        if self.situation.is_finished()
+        {
+            return Err( "game is over".to_string() );
+        }
+        if role != self.situation.get_turn()
+        {
+            return Err( format!( "{} is not to move", Situation::format_role( &role ) ) );
+        }
+        Ok( () )
+    }
+
+    fn play( &mut self, the_move: Situation::Move ) -> Option<()>
+    {
+        let next = self.situation.copy_apply( the_move.clone() )?;
+        let previous = ::std::mem::replace( &mut self.situation, next );
+        self.history.push( ( previous, the_move ) );
+        Some( () )
+    }
+}
+
+fn argument<'a>( arguments: &[&'a str], index: usize ) -> Result<&'a str, String>
+{
+    arguments.get( index ).cloned().ok_or_else( || "missing argument".to_string() )
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use game::GameSituation;
+    use othello::OthelloSituation;
+    use othello::players::DummyOthelloPlayer;
+
+    fn run( engine: &mut GtpEngine<OthelloSituation, DummyOthelloPlayer>, script: &str ) -> Vec<String>
+    {
+        let mut output = Vec::new();
+        engine.run( script.as_bytes(), &mut output ).unwrap();
+        let output = String::from_utf8( output ).unwrap();
+        assert!( output.ends_with( "\n\n" ) );
+        output[..output.len() - 2].split( "\n\n" ).map( str::to_string ).collect()
+    }
+
+    fn engine() -> GtpEngine<OthelloSituation, DummyOthelloPlayer>
+    {
+        GtpEngine::new( OthelloSituation::new(), 8, DummyOthelloPlayer::new() )
+    }
+
+    #[test]
+    fn plays_and_undoes_moves()
+    {
+        let mut engine = engine();
+        let answers = run( &mut engine, "protocol_version\n1 boardsize 8\n2 play b f5\n# a comment\n\ngenmove white\nplay w c3\nundo\nplay w e6\n3 undo\nundo\nquit\nname\n" );
+        //The dummy plays the first legal move, f4 after f5
+        assert_eq!( answers, vec![ "= 2", "=1 ", "=2 ", "= F4", "? W is not to move", "= ", "? illegal move", "=3 ", "? cannot undo", "= " ] );
+        assert_eq!( engine.situation(), &OthelloSituation::new() );
+    }
+
+    #[test]
+    fn reports_errors()
+    {
+        let mut engine = engine();
+        let answers = run( &mut engine, "boardsize 6\nboardsize x\nplay b\nplay red f5\nplay b z9\nfly\nknown_command play\nknown_command fly\nfinal_score\n" );
+        assert_eq!( answers, vec![ "? unacceptable size", "? syntax error", "? missing argument", "? syntax error", "? syntax error",
+                                   "? unknown command", "= true", "= false", "? game is not over" ] );
+    }
+
+    #[test]
+    fn plays_a_whole_game()
+    {
+        let mut engine = engine();
+        let mut script = String::from( "clear_board\n" );
+        //Passes are moves too, so a game may take more than 60
+        for _ in 0..40
+        {
+            script.push_str( "genmove b\ngenmove w\n" );
+        }
+        script.push_str( "final_score\nshowboard\n" );
+        let answers = run( &mut engine, &script );
+        assert!( engine.situation().is_finished() );
+        let score = &answers[answers.len() - 2];
+        assert_eq!( *score, format!( "= {}", engine.situation().format_result() ) );
+        assert!( answers.last().unwrap().starts_with( "= \nTurn:" ) );
+    }
+}
@@ -5,32 +5,54 @@ pub mod bitboard;
 pub mod othello;
 pub mod game;
 pub mod ai;
+pub mod gtp;
+pub mod external;
 
 
 use game::GameSituation;
 type OthelloMove = <othello::OthelloSituation as GameSituation>::Move;  
-type OthelloPlayer = game::Player< Situation = othello::OthelloSituation, Move = OthelloMove >;
+pub type OthelloPlayer = dyn game::Player< Situation = othello::OthelloSituation, Move = OthelloMove >;
 
 pub struct OthelloGame
 {
     black_player: Box<OthelloPlayer>,
     white_player: Box<OthelloPlayer>,
-    situation: othello::OthelloSituation
+    situation: othello::OthelloSituation,
+    record: othello::record::GameRecord
 }
 
 impl OthelloGame
 {
     pub fn new( black_player: Box<OthelloPlayer>, white_player: Box<OthelloPlayer> ) -> OthelloGame
     {
-        OthelloGame{ black_player, white_player, situation: othello::OthelloSituation::new() }
+        Self::with_start( black_player, white_player, othello::OthelloSituation::new() )
+    }
+
+    //A game from the given position, eg. an opening from a list
+    pub fn with_start( black_player: Box<OthelloPlayer>, white_player: Box<OthelloPlayer>, start: othello::OthelloSituation ) -> OthelloGame
+    {
+        OthelloGame{ black_player, white_player, situation: start.clone(), record: othello::record::GameRecord::with_start( start ) }
+    }
+
+    //The moves played so far and, once the game is over, the result
+    pub fn record( &self ) -> &othello::record::GameRecord
+    {
+        &self.record
     }
 
     pub fn play(&mut self) -> Option<othello::Player> 
+    {
+        self.play_observed( |_| () )
+    }
+
+    //As play, showing observer the situation before every move
+    pub fn play_observed<F: FnMut( &othello::OthelloSituation )>( &mut self, mut observer: F ) -> Option<othello::Player>
     {
         let mut previous_move: Option<OthelloMove> = None;
         while !self.situation.is_finished() 
         {
-            let ref mut player_to_move = match self.situation.get_turn()
+            observer( &self.situation );
+            let player_to_move = match self.situation.get_turn()
             {
                 othello::Player::Black => &mut self.black_player,
                 othello::Player::White => &mut self.white_player
@@ -39,24 +61,34 @@ impl OthelloGame
             previous_move = new_move.clone();
             if let Some(new_move) = new_move 
             {
-                if let Some(new_situation) = self.situation.copy_apply(new_move) 
+                if let Some(new_situation) = self.situation.copy_apply(new_move.clone()) 
                 {
                     self.situation = new_situation;
+                    self.record.push(new_move);
                 }
                 else 
                 {
                     println!("Player {} returned an illegal move {}", self.situation.get_turn(), previous_move.unwrap() );
-                    return Some(self.situation.get_turn().opposite());
+                    return self.forfeit();
                 }
             }
             else 
             {
                 println!("Player {} returned no move (giving up)", self.situation.get_turn() );
-                return Some(self.situation.get_turn().opposite());
+                return self.forfeit();
             }
         }
+        self.record.set_result( othello::record::GameResult::Discs( self.situation.get_score( othello::Player::Black ) ) );
         self.situation.get_winner()
     }
+
+    //The side to move loses
+    fn forfeit(&mut self) -> Option<othello::Player>
+    {
+        let winner = self.situation.get_turn().opposite();
+        self.record.set_result( othello::record::GameResult::Forfeit( winner.clone() ) );
+        Some(winner)
+    }
 }
 
 //Tests
@@ -73,6 +105,19 @@ mod tests
         game.play();
     }
 
+    #[test]
+    fn games_are_recorded()
+    {
+        let mut game = OthelloGame::new( Box::new(othello::players::DummyOthelloPlayer::new()), Box::new(othello::players::OthelloMinMaxPlayer::new(2)) );
+        let winner = game.play();
+        let record = game.record();
+        let final_situation = record.final_situation().unwrap();
+        assert!( final_situation.is_finished() );
+        assert_eq!( final_situation.get_winner(), winner );
+        assert_eq!( record.result(), Some( &othello::record::GameResult::Discs( final_situation.get_score( othello::Player::Black ) ) ) );
+        assert_eq!( &record.transcript().parse::<othello::record::GameRecord>().unwrap(), record );
+    }
+
     #[test]
     fn minmax_beats_dummy()
     {
@@ -84,7 +129,15 @@ mod tests
     #[test]
     fn minmax_beats_dummy2()
     {
-        let mut game = OthelloGame::new( Box::new(othello::players::OthelloMinMaxPlayer::new(3)),Box::new(othello::players::DummyOthelloPlayer::new()) );
+        let mut game = OthelloGame::new( Box::new(othello::players::OthelloMinMaxPlayer::new(4)),Box::new(othello::players::DummyOthelloPlayer::new()) );
         assert_eq!(game.play(), Some(othello::Player::Black));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn mcts_beats_dummy()
+    {
+        let mcts = othello::players::OthelloMctsPlayer::with_config( ai::MctsConfig{ seed: Some( 7 ), ..ai::MctsConfig::iterations( 200 ) } );
+        let mut game = OthelloGame::new( Box::new(othello::players::DummyOthelloPlayer::new()), Box::new(mcts) );
+        assert_eq!(game.play(), Some(othello::Player::White));
+    }
+}
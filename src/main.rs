@@ -1,9 +1,21 @@
 extern crate bit_board;
+use std::env;
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
 use bit_board::OthelloGame;
-use bit_board::othello::players::{HumanOthelloPlayer, OthelloAlphaBetaPlayer};
+use bit_board::game::perft;
+use bit_board::gtp::GtpEngine;
+use bit_board::game::MoveNotation;
+use bit_board::othello::OthelloSituation;
+use bit_board::othello::nboard::NBoardEngine;
+use bit_board::othello::server::{self, GameServer};
+use bit_board::othello::tournament::{self, Format, Sprt, Tournament};
+use bit_board::othello::players::{HumanOthelloPlayer, OthelloAlphaBetaPlayer, OthelloMctsPlayer};
+use bit_board::ai::MctsConfig;
 
 
-fn main() 
+fn play()
 {
     let mut game = OthelloGame::new( Box::new( HumanOthelloPlayer::new() ), Box::new( OthelloAlphaBetaPlayer::new(7) ) );
     match game.play()
@@ -12,3 +24,125 @@ fn main()
         Some(winner) => println!("{} won" ,winner)
     }
 }
+
+//Prints the perft numbers of the start position for depths 1 to max_depth
+fn run_perft( max_depth: u32 )
+{
+    let situation = OthelloSituation::new();
+    for depth in 1..=max_depth
+    {
+        let start = Instant::now();
+        let nodes = perft( &situation, depth );
+        let elapsed = start.elapsed();
+        println!("perft {:2} {:12} {:8.3} s {:6.1} Mnps", depth, nodes, elapsed.as_secs_f64(), nodes as f64 / elapsed.as_secs_f64() / 1e6);
+    }
+}
+
+//Speaks the NBoard protocol on stdin and stdout, learning into the book file
+fn run_nboard( book: Option<&String> ) -> io::Result<()>
+{
+    let mut engine = NBoardEngine::new();
+    if let Some(path) = book
+    {
+        engine.set_book_file(path)?;
+    }
+    let stdin = io::stdin();
+    engine.run(stdin.lock(), io::stdout())
+}
+
+//Speaks the GTP style protocol on stdin and stdout
+fn run_gtp() -> io::Result<()>
+{
+    let mut engine = GtpEngine::new( OthelloSituation::new(), 8, OthelloAlphaBetaPlayer::new(7) );
+    let stdin = io::stdin();
+    engine.run(stdin.lock(), io::stdout())
+}
+
+//Hosts a game between two network clients, a minute per move
+fn run_server( address: &str ) -> io::Result<()>
+{
+    let server = GameServer::bind(address)?;
+    println!("Listening on {}", server.local_addr());
+    let black = server.player(bit_board::othello::Player::Black, Duration::from_secs(60));
+    let white = server.player(bit_board::othello::Player::White, Duration::from_secs(60));
+    let mut game = OthelloGame::new( Box::new(black), Box::new(white) );
+    server.play(&mut game);
+    println!("{}", game.record());
+    Ok(())
+}
+
+//Plays a game at a server with the alpha-beta player. With the token of an
+//earlier connection the seat is taken back
+fn run_connect( address: &str, role: &str, token: Option<&String> ) -> io::Result<()>
+{
+    let role = OthelloSituation::parse_role(role).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the role is black or white"))?;
+    let mut token = token.cloned();
+    match server::run_client(address, role, &mut token, &mut OthelloAlphaBetaPlayer::new(7))
+    {
+        Ok(result) => println!("Result {}", result),
+        Err(error) => match token
+        {
+            Some(token) => println!("{}, reconnect with token {}", error, token),
+            None => return Err(error)
+        }
+    }
+    Ok(())
+}
+
+//Round robin between a few configurations, from the openings of the file if
+//given, one transcript per line
+fn run_tournament( rounds: u32, openings: Option<&String> ) -> io::Result<()>
+{
+    let mut tournament = Tournament::new(Format::RoundRobin, rounds);
+    tournament.add_player("alphabeta4", || Box::new(OthelloAlphaBetaPlayer::new(4)));
+    tournament.add_player("alphabeta6", || Box::new(OthelloAlphaBetaPlayer::new(6)));
+    tournament.add_player("mcts", || Box::new(OthelloMctsPlayer::with_config(MctsConfig::time(Duration::from_millis(200)))));
+    if let Some(path) = openings
+    {
+        let text = fs::read_to_string(path)?;
+        tournament.set_openings(tournament::openings_from_transcripts(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?);
+    }
+    tournament.set_sprt(Sprt::new(0.0, 50.0));
+    print!("{}", tournament.run());
+    Ok(())
+}
+
+fn main()
+{
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(|command| command.as_str())
+    {
+        None => play(),
+        Some("perft") => match args.get(1).map(|depth| depth.parse::<u32>())
+        {
+            None => run_perft(9),
+            Some(Ok(depth)) => run_perft(depth),
+            Some(Err(_)) => println!("Usage: bit_board perft [depth]")
+        },
+        Some("nboard") => if let Err(error) = run_nboard(args.get(1))
+        {
+            eprintln!("{}", error);
+        },
+        Some("gtp") => if let Err(error) = run_gtp()
+        {
+            eprintln!("{}", error);
+        },
+        Some("serve") => if let Err(error) = run_server(args.get(1).map_or("0.0.0.0:5077", |address| address.as_str()))
+        {
+            eprintln!("{}", error);
+        },
+        Some("connect") if args.len() == 3 || args.len() == 4 => if let Err(error) = run_connect(&args[1], &args[2], args.get(3))
+        {
+            eprintln!("{}", error);
+        },
+        Some("tournament") => match args.get(1).map_or(Ok(10), |rounds| rounds.parse::<u32>())
+        {
+            Ok(rounds) => if let Err(error) = run_tournament(rounds, args.get(2))
+            {
+                eprintln!("{}", error);
+            },
+            Err(_) => println!("Usage: bit_board tournament [rounds [openings]]")
+        },
+        Some(_) => println!("Usage: bit_board [perft [depth] | nboard [book] | gtp | serve [address] | connect address black|white [token] | tournament [rounds [openings]]]")
+    }
+}
@@ -0,0 +1,365 @@
+//Opening book. Positions are keyed by the hash of their canonical form, so
+//that the symmetric variants of an opening share their entries, and the moves
+//are kept in the orientation of the canonical form. Scores are in discs for
+//the side to move.
+//
+//The file format is a header of 8 magic bytes and the number of moves as u32,
+//followed by 17 byte records, all little endian:
+//  key u64, square u8 ( 8 * row + column, 64 for a pass ), visits u32,
+//  score f32
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter, ErrorKind};
+use std::path::Path;
+use super::{OthelloSituation, OthelloMove, Player};
+use super::record::{GameRecord, GameResult, RecordError};
+use super::super::ai::Random;
+use super::super::bitboard::Coord;
+use super::super::game::GameSituation;
+
+const FILE_MAGIC: &[u8; 8] = b"OTHBOOK2";
+const MOVE_SIZE: usize = 17;
+const PASS_SQUARE: u8 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookMove
+{
+    pub the_move: OthelloMove,
+    //Average result of the games that played the move, or its search score
+    pub score: f32,
+    //Number of games that played the move, zero if only searched
+    pub visits: u32
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpeningBook
+{
+    positions: HashMap<u64, Vec<BookMove>>
+}
+
+fn invalid_data( message: String ) -> io::Error
+{
+    io::Error::new( ErrorKind::InvalidData, message )
+}
+
+impl OpeningBook
+{
+    pub fn new() -> OpeningBook
+    {
+        OpeningBook{ positions: HashMap::new() }
+    }
+
+    //Number of positions
+    pub fn len( &self ) -> usize
+    {
+        self.positions.len()
+    }
+
+    pub fn is_empty( &self ) -> bool
+    {
+        self.positions.is_empty()
+    }
+
+    //The book moves of the position, in its own orientation
+    pub fn lookup( &self, situation: &OthelloSituation ) -> Vec<BookMove>
+    {
+        let ( canonical, symmetry ) = situation.canonical();
+        let back = symmetry.inverse();
+        self.positions.get( &canonical.hash_key() ).map( |moves| moves.iter()
+            .map( |book_move| BookMove{ the_move: book_move.the_move.transform( back ), ..book_move.clone() } )
+            //Guards against hash collisions
+            .filter( |book_move| situation.get_moves().any( |a_move| a_move == book_move.the_move ) )
+            .collect() ).unwrap_or_default()
+    }
+
+    fn entry( &mut self, situation: &OthelloSituation, a_move: &OthelloMove ) -> &mut BookMove
+    {
+        let ( canonical, symmetry ) = situation.canonical();
+        let the_move = a_move.transform( symmetry );
+        let moves = self.positions.entry( canonical.hash_key() ).or_default();
+        match moves.iter().position( |book_move| book_move.the_move == the_move )
+        {
+            Some( index ) => &mut moves[index],
+            None =>
+            {
+                moves.push( BookMove{ the_move, score: 0.0, visits: 0 } );
+                moves.last_mut().unwrap()
+            }
+        }
+    }
+
+    //Adds the first plies moves of a game, each scored by the result for the
+    //player who made it. Games without a result are left out; returns
+    //whether the game was added
+    pub fn add_record( &mut self, record: &GameRecord, plies: usize ) -> Result<bool, RecordError>
+    {
+        let black_result = match record.result()
+        {
+            Some( &GameResult::Discs( discs ) ) => discs,
+            Some( GameResult::Forfeit( winner ) ) => if *winner == Player::Black { 64 } else { -64 },
+            None => return Ok( false )
+        };
+        let situations = record.replay()?;
+        for ( situation, a_move ) in situations.iter().zip( record.moves() ).take( plies )
+        {
+            if *a_move == OthelloMove::Pass
+            {
+                continue;
+            }
+            let result = if situation.get_turn() == Player::Black { black_result } else { -black_result };
+            let book_move = self.entry( situation, a_move );
+            book_move.visits += 1;
+            book_move.score += ( result as f32 - book_move.score ) / book_move.visits as f32;
+        }
+        Ok( true )
+    }
+
+    //Sets the score of a move, keeping its visits
+    pub fn set_score( &mut self, situation: &OthelloSituation, a_move: &OthelloMove, score: i32 )
+    {
+        self.entry( situation, a_move ).score = score as f32;
+    }
+
+    //Scores every move of the position by searching the position after it,
+    //then goes on with the width best moves for plies moves in all.
+    //evaluate gives the score of a position for its side to move
+    pub fn add_searched<F>( &mut self, situation: &OthelloSituation, plies: u32, width: usize, mut evaluate: F )
+        where F: FnMut( &OthelloSituation ) -> i32
+    {
+        self.expand( situation, plies, width, &mut evaluate );
+    }
+
+    fn expand( &mut self, situation: &OthelloSituation, plies: u32, width: usize, evaluate: &mut dyn FnMut( &OthelloSituation ) -> i32 )
+    {
+        if plies == 0 || situation.is_finished()
+        {
+            return;
+        }
+        let mut scored = situation.get_moves().map( |a_move|
+        {
+            let next = situation.copy_apply( a_move.clone() ).unwrap();
+            ( -evaluate( &next ), a_move, next )
+        }).collect::<Vec<_>>();
+        for &( score, ref a_move, _ ) in scored.iter()
+        {
+            self.set_score( situation, a_move, score );
+        }
+        scored.sort_by_key( |&( score, _, _ )| -score );
+        for ( _, _, next ) in scored.into_iter().take( width )
+        {
+            self.expand( &next, plies - 1, width, evaluate );
+        }
+    }
+
+    //Picks a book move for the position, None if it is not in the book. With
+    //variety zero the best scored move is played, ties going to the most
+    //played one. Otherwise moves are drawn with weights exp( -loss / variety ),
+    //loss being how many discs a move scores below the best one
+    pub fn choose_move( &self, situation: &OthelloSituation, variety: f64, random: &mut Random ) -> Option<OthelloMove>
+    {
+        let moves = self.lookup( situation );
+        let best = moves.iter().max_by( |a, b| a.score.total_cmp( &b.score ).then( a.visits.cmp( &b.visits ) ) )?.clone();
+        if variety <= 0.0
+        {
+            return Some( best.the_move );
+        }
+        let weights = moves.iter().map( |book_move| ( ( book_move.score - best.score ) as f64 / variety ).exp() ).collect::<Vec<_>>();
+        let mut pick = random.next_f64() * weights.iter().sum::<f64>();
+        for ( book_move, weight ) in moves.iter().zip( weights )
+        {
+            if pick < weight
+            {
+                return Some( book_move.the_move.clone() );
+            }
+            pick -= weight;
+        }
+        Some( best.the_move )
+    }
+
+    pub fn read_from<R: Read>( mut reader: R ) -> io::Result<OpeningBook>
+    {
+        let mut header = [0u8; 12];
+        reader.read_exact( &mut header )?;
+        if &header[..8] != FILE_MAGIC
+        {
+            return Err( invalid_data( "not an opening book file".to_string() ) );
+        }
+        let count = u32::from_le_bytes( [ header[8], header[9], header[10], header[11] ] );
+        let mut book = OpeningBook::new();
+        let mut bytes = [0u8; MOVE_SIZE];
+        for _ in 0..count
+        {
+            reader.read_exact( &mut bytes )?;
+            let mut key = [0u8; 8];
+            key.copy_from_slice( &bytes[..8] );
+            let the_move = match bytes[8]
+            {
+                PASS_SQUARE => OthelloMove::Pass,
+                square => OthelloMove::Coord( Coord::new( square as u32 / 8, square as u32 % 8 )
+                    .ok_or_else( || invalid_data( format!( "invalid square {}", square ) ) )? )
+            };
+            let visits = u32::from_le_bytes( [ bytes[9], bytes[10], bytes[11], bytes[12] ] );
+            let score = f32::from_le_bytes( [ bytes[13], bytes[14], bytes[15], bytes[16] ] );
+            if !score.is_finite()
+            {
+                return Err( invalid_data( format!( "invalid score {}", score ) ) );
+            }
+            book.positions.entry( u64::from_le_bytes( key ) ).or_default().push( BookMove{ the_move, score, visits } );
+        }
+        Ok( book )
+    }
+
+    //Positions are written by key, so equal books give equal files
+    pub fn write_to<W: Write>( &self, mut writer: W ) -> io::Result<()>
+    {
+        let mut keys = self.positions.keys().collect::<Vec<_>>();
+        keys.sort();
+        let count = self.positions.values().map( Vec::len ).sum::<usize>() as u32;
+        writer.write_all( FILE_MAGIC )?;
+        writer.write_all( &count.to_le_bytes() )?;
+        for key in keys
+        {
+            for book_move in self.positions[key].iter()
+            {
+                let square = match book_move.the_move
+                {
+                    OthelloMove::Pass => PASS_SQUARE,
+                    OthelloMove::Coord( coord ) => ( coord.get_row() * 8 + coord.get_col() ) as u8
+                };
+                writer.write_all( &key.to_le_bytes() )?;
+                writer.write_all( &[ square ] )?;
+                writer.write_all( &book_move.visits.to_le_bytes() )?;
+                writer.write_all( &book_move.score.to_le_bytes() )?;
+            }
+        }
+        Ok( () )
+    }
+
+    pub fn load<P: AsRef<Path>>( path: P ) -> io::Result<OpeningBook>
+    {
+        OpeningBook::read_from( BufReader::new( File::open( path )? ) )
+    }
+
+    pub fn save<P: AsRef<Path>>( &self, path: P ) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new( File::create( path )? );
+        self.write_to( &mut writer )?;
+        writer.flush()
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::str::FromStr;
+    use bitboard::Symmetry;
+
+    fn play( moves: &[&str] ) -> OthelloSituation
+    {
+        moves.iter().fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str( a_move ).unwrap() ).unwrap() )
+    }
+
+    fn record( transcript: &str, black_result: i32 ) -> GameRecord
+    {
+        let mut record = transcript.parse::<GameRecord>().unwrap();
+        record.set_result( GameResult::Discs( black_result ) );
+        record
+    }
+
+    #[test]
+    fn records_are_averaged_across_symmetries()
+    {
+        let mut book = OpeningBook::new();
+        //f5 and d3 are the same opening move under symmetry
+        assert_eq!( book.add_record( &record( "f5d6", 10 ), 10 ), Ok( true ) );
+        assert_eq!( book.add_record( &record( "d3c5", -4 ), 10 ), Ok( true ) );
+        assert_eq!( book.add_record( &"f5".parse::<GameRecord>().unwrap(), 10 ), Ok( false ) );
+        assert_eq!( book.len(), 2 );
+
+        let start = book.lookup( &OthelloSituation::new() );
+        assert_eq!( start.len(), 2 );
+        assert!( start.iter().all( |book_move| book_move.visits == 1 ) );
+        let after_f5 = book.lookup( &play( &[ "f5" ] ) );
+        assert_eq!( after_f5, vec![ BookMove{ the_move: OthelloMove::from_str( "d6" ).unwrap(), score: -3.0, visits: 2 } ] );
+        //c4 is f5 turned by half a turn
+        let after_c4 = book.lookup( &play( &[ "c4" ] ) );
+        assert_eq!( after_c4[0].the_move, OthelloMove::from_str( "d6" ).unwrap().transform( Symmetry::Rotate180 ) );
+        assert_eq!( after_c4[0].score, -3.0 );
+    }
+
+    #[test]
+    fn averages_are_not_rounded()
+    {
+        let mut book = OpeningBook::new();
+        for black_result in [ 1, 0, 0 ].iter()
+        {
+            book.add_record( &record( "f5", *black_result ), 1 ).unwrap();
+        }
+        let start = book.lookup( &OthelloSituation::new() );
+        assert_eq!( start[0].visits, 3 );
+        assert!( ( start[0].score - 1.0 / 3.0 ).abs() < 1e-6 );
+    }
+
+    #[test]
+    fn searched_moves_are_scored()
+    {
+        let mut book = OpeningBook::new();
+        let mut evaluations = 0;
+        //Discs of the side to move
+        book.add_searched( &OthelloSituation::new(), 3, 2, |situation|
+        {
+            evaluations += 1;
+            situation.get_score( situation.get_turn() )
+        });
+        //4 moves at the root, then the 2 best of each ply, fewer as the
+        //symmetric positions are merged
+        assert!( book.len() > 1 && book.len() <= 1 + 2 + 4 );
+        assert!( evaluations > 4 );
+        let start = book.lookup( &OthelloSituation::new() );
+        assert_eq!( start.len(), 4 );
+        assert!( start.iter().all( |book_move| book_move.score == 3.0 && book_move.visits == 0 ) );
+    }
+
+    #[test]
+    fn variety_spreads_the_choice()
+    {
+        let mut book = OpeningBook::new();
+        let situation = play( &[ "f5" ] );
+        for ( square, score ) in [ ( "d6", 2 ), ( "f6", 0 ), ( "f4", -20 ) ].iter()
+        {
+            book.set_score( &situation, &OthelloMove::from_str( square ).unwrap(), *score );
+        }
+        let mut random = Random::new( 5 );
+        assert_eq!( book.choose_move( &situation, 0.0, &mut random ), Some( OthelloMove::from_str( "d6" ).unwrap() ) );
+        let picks = ( 0..1000 ).map( |_| book.choose_move( &situation, 2.0, &mut random ).unwrap() ).collect::<Vec<_>>();
+        let count = |square: &str| picks.iter().filter( |&a_move| *a_move == OthelloMove::from_str( square ).unwrap() ).count();
+        assert!( count( "d6" ) > count( "f6" ) );
+        assert!( count( "f6" ) > 100 );
+        assert!( count( "f4" ) < 10 );
+        assert_eq!( book.choose_move( &OthelloSituation::new(), 2.0, &mut random ), None );
+    }
+
+    #[test]
+    fn book_file_round_trips()
+    {
+        let mut book = OpeningBook::new();
+        book.add_record( &record( "f5d6c3d3c4", 6 ), 4 ).unwrap();
+        book.add_searched( &OthelloSituation::new(), 2, 4, |situation| situation.get_score( situation.get_turn() ) );
+        let mut bytes = Vec::new();
+        book.write_to( &mut bytes ).unwrap();
+        assert_eq!( ( bytes.len() - 12 ) % MOVE_SIZE, 0 );
+        assert_eq!( OpeningBook::read_from( &bytes[..] ).unwrap(), book );
+
+        assert_eq!( OpeningBook::read_from( &bytes[..30] ).unwrap_err().kind(), ErrorKind::UnexpectedEof );
+        let mut not_a_number = bytes.clone();
+        not_a_number[25..29].copy_from_slice( &f32::NAN.to_le_bytes() );
+        assert_eq!( OpeningBook::read_from( &not_a_number[..] ).unwrap_err().kind(), ErrorKind::InvalidData );
+        bytes[20] = 65;
+        assert_eq!( OpeningBook::read_from( &bytes[..] ).unwrap_err().kind(), ErrorKind::InvalidData );
+        assert_eq!( OpeningBook::read_from( &b"OTHBOOK1\0\0\0\0"[..] ).unwrap_err().kind(), ErrorKind::InvalidData );
+    }
+}
@@ -0,0 +1,351 @@
+//Exact endgame solver. Near the end of the game the tree is small enough to
+//be searched to the end, so instead of evaluating positions the final disc
+//differential (as in OthelloSituation::get_score) is computed exactly.
+//Works directly on the bitboards of the side to move and the opponent.
+
+use super::{OthelloSituation, OthelloMove, generate_moves, delta_for_move};
+use super::super::ai::{SearchControl, SearchLimits};
+use super::super::bitboard::{BitBoard, Coord};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndgameMode
+{
+    //Only finds out whether the position is won, lost or drawn. Faster,
+    //scores are 1, 0 or -1
+    WinLossDraw,
+    //Finds the exact final disc differential
+    Exact
+}
+
+//Below this many empties the simpler last empties routine is used
+const LAST_EMPTIES: u32 = 4;
+//Above this many empties moves are ordered by opponent mobility
+const FASTEST_FIRST_EMPTIES: u32 = 7;
+
+const QUADRANTS: [u64; 4] = [ 0x0000_0000_0F0F_0F0F, 0x0000_0000_F0F0_F0F0, 0x0F0F_0F0F_0000_0000, 0xF0F0_F0F0_0000_0000 ];
+
+pub struct EndgameSolver
+{
+    mode: EndgameMode,
+    control: SearchControl
+}
+
+impl EndgameSolver
+{
+    pub fn new( mode: EndgameMode ) -> EndgameSolver
+    {
+        EndgameSolver{ mode, control: SearchControl::new() }
+    }
+
+    //Nodes visited by the last solve
+    pub fn nodes( &self ) -> u64
+    {
+        self.control.nodes()
+    }
+
+    //Returns the best move and its score from the point of view of the side
+    //to move, or None if the time or node limit ran out first. The depth
+    //limit does not apply, the game is always searched to its end
+    pub fn solve( &mut self, situation: &OthelloSituation, limits: SearchLimits ) -> Option<( Option<OthelloMove>, i32 )>
+    {
+        self.control.start( SearchLimits{ max_depth: None, ..limits } );
+        let own = situation.get_own_board();
+        let opponent = situation.get_opponent_board();
+        let ( alpha, beta ) = match self.mode
+        {
+            EndgameMode::WinLossDraw => ( -1, 1 ),
+            EndgameMode::Exact => ( -65, 65 )
+        };
+
+        let moves = situation.moves;
+        if moves.is_empty()
+        {
+            if generate_moves( opponent, own ).is_empty()
+            {
+                return Some( ( None, self.clamp( final_score( own, opponent ) ) ) );
+            }
+            let score = -self.search( opponent, own, -beta, -alpha, true );
+            if self.control.is_stopped()
+            {
+                return None;
+            }
+            return Some( ( Some( OthelloMove::Pass ), self.clamp( score ) ) );
+        }
+
+        let mut alpha = alpha;
+        let mut best_move = None;
+        let mut best_score = -65;
+        for move_board in self.order_moves( own, opponent, moves )
+        {
+            let flips = delta_for_move( own, opponent, move_board );
+            let score = -self.search( opponent ^ flips, own | flips | move_board, -beta, -alpha, false );
+            if self.control.is_stopped()
+            {
+                return None;
+            }
+            if score > best_score
+            {
+                best_score = score;
+                best_move = move_board.first_one().map( OthelloMove::Coord );
+            }
+            if score > alpha
+            {
+                alpha = score;
+            }
+            if alpha >= beta
+            {
+                break;
+            }
+        }
+        Some( ( best_move, self.clamp( best_score ) ) )
+    }
+
+    fn clamp( &self, score: i32 ) -> i32
+    {
+        match self.mode
+        {
+            EndgameMode::WinLossDraw => score.signum(),
+            EndgameMode::Exact => score
+        }
+    }
+
+    fn search( &mut self, own: BitBoard, opponent: BitBoard, mut alpha: i32, beta: i32, passed: bool ) -> i32
+    {
+        if self.control.count_node()
+        {
+            return 0;
+        }
+        let empties = !( own | opponent );
+        if empties.count_ones() as u32 <= LAST_EMPTIES
+        {
+            return self.search_last( own, opponent, empties, alpha, beta, passed );
+        }
+
+        let moves = generate_moves( own, opponent );
+        if moves.is_empty()
+        {
+            if passed
+            {
+                return final_score( own, opponent );
+            }
+            return -self.search( opponent, own, -beta, -alpha, true );
+        }
+
+        let mut best_score = -65;
+        for move_board in self.order_moves( own, opponent, moves )
+        {
+            let flips = delta_for_move( own, opponent, move_board );
+            let score = -self.search( opponent ^ flips, own | flips | move_board, -beta, -alpha, false );
+            if score > best_score
+            {
+                best_score = score;
+                if score > alpha
+                {
+                    alpha = score;
+                    if alpha >= beta
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+        best_score
+    }
+
+    //The last few empties: no move generation or ordering, every empty square
+    //is just tried in turn (odd regions first)
+    fn search_last( &mut self, own: BitBoard, opponent: BitBoard, empties: BitBoard, mut alpha: i32, beta: i32, passed: bool ) -> i32
+    {
+        if self.control.count_node()
+        {
+            return 0;
+        }
+        let mut best_score = -65;
+        let mut has_moves = false;
+        for coord in parity_order( empties, empties )
+        {
+            let move_board = BitBoard::empty().with_one_at( coord );
+            let flips = delta_for_move( own, opponent, move_board );
+            if flips.is_empty()
+            {
+                continue;
+            }
+            has_moves = true;
+            let score = -self.search_last( opponent ^ flips, own | flips | move_board, empties ^ move_board, -beta, -alpha, false );
+            if score > best_score
+            {
+                best_score = score;
+                if score > alpha
+                {
+                    alpha = score;
+                    if alpha >= beta
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+        if has_moves
+        {
+            best_score
+        }
+        else if passed || empties.is_empty()
+        {
+            final_score( own, opponent )
+        }
+        else
+        {
+            -self.search_last( opponent, own, empties, -beta, -alpha, true )
+        }
+    }
+
+    //Fastest first: moves that leave the opponent with the fewest replies
+    //first, since those lines have the smallest trees. Ties and the smaller
+    //positions are ordered by parity
+    fn order_moves( &self, own: BitBoard, opponent: BitBoard, moves: BitBoard ) -> Vec<BitBoard>
+    {
+        let empties = !( own | opponent );
+        let mut ordered = parity_order( moves, empties ).into_iter().map( |coord| BitBoard::empty().with_one_at( coord ) ).collect::<Vec<_>>();
+        if empties.count_ones() as u32 > FASTEST_FIRST_EMPTIES
+        {
+            ordered.sort_by_cached_key( |&move_board|
+            {
+                let flips = delta_for_move( own, opponent, move_board );
+                generate_moves( opponent ^ flips, own | flips | move_board ).count_ones()
+            });
+        }
+        ordered
+    }
+}
+
+//Disc differential from the point of view of own
+fn final_score( own: BitBoard, opponent: BitBoard ) -> i32
+{
+    own.count_ones() - opponent.count_ones()
+}
+
+//Squares in quadrants with an odd number of empties first: playing there
+//tends to leave the opponent the bad last move of the region
+fn parity_order( squares: BitBoard, empties: BitBoard ) -> Vec<Coord>
+{
+    let mut odd = Vec::new();
+    let mut even = Vec::new();
+    for &quadrant in QUADRANTS.iter()
+    {
+        let quadrant = BitBoard::from_bits( quadrant );
+        let region = squares & quadrant;
+        if ( empties & quadrant ).count_ones() % 2 == 1
+        {
+            odd.extend( region );
+        }
+        else
+        {
+            even.extend( region );
+        }
+    }
+    odd.extend( even );
+    odd
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use test::Bencher;
+    use super::*;
+    use game::GameSituation;
+
+    const UNLIMITED: SearchLimits = SearchLimits{ max_depth: None, time: None, nodes: None };
+
+    //Plain minimax to the end of the game
+    fn brute_force( situation: &OthelloSituation ) -> i32
+    {
+        if situation.is_finished()
+        {
+            return situation.get_score( situation.get_turn() );
+        }
+        situation.get_moves()
+            .map( |a_move| -brute_force( &situation.copy_apply( a_move ).unwrap() ) )
+            .max().unwrap()
+    }
+
+    //Positions with few empties from a deterministic game
+    fn endgame_positions() -> Vec<OthelloSituation>
+    {
+        let mut positions = Vec::new();
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            if situation.empty_squares() <= 9
+            {
+                positions.push( situation.clone() );
+            }
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            situation = situation.copy_apply( moves[ ( ply * 5 ) % moves.len() ].clone() ).unwrap();
+            ply += 1;
+        }
+        positions.push( situation );
+        positions
+    }
+
+    #[test]
+    fn exact_matches_brute_force()
+    {
+        let mut solver = EndgameSolver::new( EndgameMode::Exact );
+        for situation in endgame_positions()
+        {
+            let expected = brute_force( &situation );
+            let ( best_move, score ) = solver.solve( &situation, UNLIMITED ).unwrap();
+            assert_eq!( score, expected );
+            if let Some( best_move ) = best_move
+            {
+                let child = situation.copy_apply( best_move ).unwrap();
+                assert_eq!( -brute_force( &child ), expected );
+            }
+        }
+    }
+
+    #[test]
+    fn win_loss_draw_matches_exact()
+    {
+        let mut exact = EndgameSolver::new( EndgameMode::Exact );
+        let mut win_loss_draw = EndgameSolver::new( EndgameMode::WinLossDraw );
+        for situation in endgame_positions()
+        {
+            assert_eq!( win_loss_draw.solve( &situation, UNLIMITED ).unwrap().1, exact.solve( &situation, UNLIMITED ).unwrap().1.signum() );
+        }
+    }
+
+    #[test]
+    fn limits_stop_the_solver()
+    {
+        let situation = endgame_positions().remove( 0 );
+        let mut solver = EndgameSolver::new( EndgameMode::Exact );
+        assert_eq!( solver.solve( &situation, SearchLimits::nodes( 10 ) ), None );
+        assert_eq!( solver.solve( &situation, SearchLimits::depth( 1 ) ).unwrap().1, brute_force( &situation ) );
+        assert!( solver.nodes() > 10 );
+    }
+
+    #[test]
+    fn parity_puts_odd_regions_first()
+    {
+        let squares = ["a1", "b1", "h8"].iter().fold( BitBoard::empty(), |board, square| board.with_one_at( Coord::from_str( square ).unwrap() ) );
+        assert_eq!( parity_order( squares, squares ), vec![ Coord::from_str( "h8" ).unwrap(), Coord::from_str( "a1" ).unwrap(), Coord::from_str( "b1" ).unwrap() ] );
+    }
+
+    #[bench]
+    fn bench_solve_14_empties(b: &mut Bencher) {
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while situation.empty_squares() > 14
+        {
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            situation = situation.copy_apply( moves[ ( ply * 5 ) % moves.len() ].clone() ).unwrap();
+            ply += 1;
+        }
+        let mut solver = EndgameSolver::new( EndgameMode::Exact );
+        b.iter(|| solver.solve( &situation, UNLIMITED ));
+    }
+}
@@ -0,0 +1,427 @@
+//Feature based evaluator. The evaluation is a weighted sum of features of the
+//position, seen from the side to move. The weights depend on the phase of
+//the game and can be read from a weights file, so that they can be tuned
+//without touching the code.
+//
+//Weights file format: a header line "bit_board-weights <phases> <features>"
+//followed by lines "<phase> <feature> <weight>". Features left out have zero
+//weight, lines starting with # are comments.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use super::{OthelloSituation, generate_moves};
+use super::super::ai::SituationEvaluator;
+use super::super::bitboard::{BitBoard, Coord, Direction};
+use super::super::game::GameSituation;
+
+//The game is split into phases by the number of discs on the board
+pub const PHASES: usize = 4;
+
+//Scalar features, all of them own minus opponent
+pub const MOBILITY: usize = 0;
+pub const POTENTIAL_MOBILITY: usize = 1;
+pub const FRONTIER: usize = 2;
+pub const CORNERS: usize = 3;
+pub const X_SQUARES: usize = 4;
+pub const C_SQUARES: usize = 5;
+pub const STABLE: usize = 6;
+pub const DISCS: usize = 7;
+const SCALAR_FEATURES: usize = 8;
+
+//Edge patterns: one feature per configuration of the 8 squares of an edge,
+//counting how many of the four edges are in that configuration. The
+//configuration is a base 3 number with 0 for empty, 1 for own and 2 for
+//opponent, edges read clockwise starting from a corner
+pub const EDGE_PATTERNS: usize = SCALAR_FEATURES;
+pub const EDGE_CONFIGURATIONS: usize = 6561;
+
+pub const FEATURES: usize = SCALAR_FEATURES + EDGE_CONFIGURATIONS;
+
+const FILE_HEADER: &str = "bit_board-weights";
+
+const CORNER_MASK: u64 = 0x8100_0000_0000_0081;
+const EDGES: [[u32; 8]; 4] = [ [ 0, 1, 2, 3, 4, 5, 6, 7 ],
+                               [ 7, 15, 23, 31, 39, 47, 55, 63 ],
+                               [ 63, 62, 61, 60, 59, 58, 57, 56 ],
+                               [ 56, 48, 40, 32, 24, 16, 8, 0 ] ];
+//Corner, its X-square and its C-squares
+const CORNER_REGIONS: [( u32, u32, [u32; 2] ); 4] = [ ( 0, 9, [ 1, 8 ] ),
+                                                      ( 7, 14, [ 6, 15 ] ),
+                                                      ( 56, 49, [ 48, 57 ] ),
+                                                      ( 63, 54, [ 55, 62 ] ) ];
+
+pub fn phase( situation: &OthelloSituation ) -> usize
+{
+    let discs = 64 - situation.empty_squares() as usize;
+    ( ( discs - 4 ) * PHASES / 61 ).min( PHASES - 1 )
+}
+
+fn neighbours( board: BitBoard ) -> BitBoard
+{
+    Direction::all().iter().fold( BitBoard::empty(), |result, &direction| result | board.shift( direction ) )
+}
+
+//Squares from which all the squares in direction up to the edge are occupied
+fn filled_towards( occupied: BitBoard, direction: Direction ) -> BitBoard
+{
+    let at_edge = !( !BitBoard::empty() ).shift( direction.opposite() );
+    let mut filled = occupied;
+    for _ in 0..7
+    {
+        filled = occupied & ( filled.shift( direction.opposite() ) | at_edge );
+    }
+    filled
+}
+
+//Discs that can never be flipped: along each of the four lines through the
+//disc the line is full, or the disc is next to the edge or to a stable own disc
+pub fn stable_discs( own: BitBoard, opponent: BitBoard ) -> BitBoard
+{
+    let occupied = own | opponent;
+    let axes = [ Direction::Right, Direction::Up, Direction::UpRight, Direction::UpLeft ];
+    let full_lines = axes.iter()
+        .map( |&direction| filled_towards( occupied, direction ) & filled_towards( occupied, direction.opposite() ) )
+        .collect::<Vec<_>>();
+    let mut stable = BitBoard::empty();
+    loop
+    {
+        let mut new_stable = own;
+        for ( &direction, &full_line ) in axes.iter().zip( full_lines.iter() )
+        {
+            let mut protected = full_line;
+            for &side in [ direction, direction.opposite() ].iter()
+            {
+                let at_edge = !( !BitBoard::empty() ).shift( side.opposite() );
+                protected |= at_edge | stable.shift( side.opposite() );
+            }
+            new_stable &= protected;
+        }
+        if new_stable == stable
+        {
+            return stable;
+        }
+        stable = new_stable;
+    }
+}
+
+fn edge_configuration( own: BitBoard, opponent: BitBoard, edge: &[u32; 8] ) -> usize
+{
+    edge.iter().rev().fold( 0, |index, &square|
+    {
+        let coord = Coord::new( square / 8, square % 8 ).unwrap();
+        let digit = if own.get_value_at( coord ) { 1 } else if opponent.get_value_at( coord ) { 2 } else { 0 };
+        index * 3 + digit
+    })
+}
+
+fn compute_features( situation: &OthelloSituation ) -> ( [f64; SCALAR_FEATURES], [usize; 4] )
+{
+    let own = situation.get_own_board();
+    let opponent = situation.get_opponent_board();
+    let empty = !( own | opponent );
+    let corners = BitBoard::from_bits( CORNER_MASK );
+    let difference = |own_count: i32, opponent_count: i32| ( own_count - opponent_count ) as f64;
+
+    let mut scalars = [0.0; SCALAR_FEATURES];
+    scalars[MOBILITY] = difference( situation.moves.count_ones(), generate_moves( opponent, own ).count_ones() );
+    scalars[POTENTIAL_MOBILITY] = difference( ( neighbours( opponent ) & empty ).count_ones(), ( neighbours( own ) & empty ).count_ones() );
+    let next_to_empty = neighbours( empty );
+    scalars[FRONTIER] = difference( ( own & next_to_empty ).count_ones(), ( opponent & next_to_empty ).count_ones() );
+    scalars[CORNERS] = difference( ( own & corners ).count_ones(), ( opponent & corners ).count_ones() );
+    for &( corner, x_square, c_squares ) in CORNER_REGIONS.iter()
+    {
+        //X- and C-squares only matter while the corner is still open
+        if ( empty.bits() >> corner ) & 1 == 0
+        {
+            continue;
+        }
+        let square_value = |square: u32| ( ( own.bits() >> square ) & 1 ) as f64 - ( ( opponent.bits() >> square ) & 1 ) as f64;
+        scalars[X_SQUARES] += square_value( x_square );
+        scalars[C_SQUARES] += c_squares.iter().map( |&square| square_value( square ) ).sum::<f64>();
+    }
+    scalars[STABLE] = difference( stable_discs( own, opponent ).count_ones(), stable_discs( opponent, own ).count_ones() );
+    scalars[DISCS] = difference( own.count_ones(), opponent.count_ones() );
+
+    let mut edges = [0; 4];
+    for ( index, edge ) in EDGES.iter().enumerate()
+    {
+        edges[index] = edge_configuration( own, opponent, edge );
+    }
+    ( scalars, edges )
+}
+
+//The features of a position as ( feature, value ) pairs, zeros left out
+pub fn features( situation: &OthelloSituation ) -> Vec<( usize, f64 )>
+{
+    let ( scalars, edges ) = compute_features( situation );
+    let mut result = scalars.iter().cloned().enumerate().filter( |&( _, value )| value != 0.0 ).collect::<Vec<_>>();
+    for edge in edges.iter()
+    {
+        let feature = EDGE_PATTERNS + edge;
+        match result.iter_mut().find( |&&mut ( existing, _ )| existing == feature )
+        {
+            Some( entry ) => entry.1 += 1.0,
+            None => result.push( ( feature, 1.0 ) )
+        }
+    }
+    result
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Weights
+{
+    phases: Vec<Vec<f64>>
+}
+
+fn invalid_data( message: String ) -> io::Error
+{
+    io::Error::new( io::ErrorKind::InvalidData, message )
+}
+
+impl Weights
+{
+    pub fn zero() -> Weights
+    {
+        Weights{ phases: vec![ vec![ 0.0; FEATURES ]; PHASES ] }
+    }
+
+    pub fn get( &self, phase: usize, feature: usize ) -> f64
+    {
+        self.phases[phase][feature]
+    }
+
+    pub fn set( &mut self, phase: usize, feature: usize, weight: f64 )
+    {
+        self.phases[phase][feature] = weight;
+    }
+
+    pub fn dot( &self, phase: usize, features: &[( usize, f64 )] ) -> f64
+    {
+        features.iter().map( |&( feature, value )| self.phases[phase][feature] * value ).sum()
+    }
+
+    pub fn read_from<R: BufRead>( reader: R ) -> io::Result<Weights>
+    {
+        let mut lines = reader.lines().enumerate()
+            .filter( |( _, line )| line.as_ref().map( |line| !line.trim().is_empty() && !line.trim_start().starts_with( '#' ) ).unwrap_or( true ) );
+
+        let header = match lines.next()
+        {
+            Some( ( _, line ) ) => line?,
+            None => return Err( invalid_data( "Empty weights file".to_string() ) )
+        };
+        let expected_header = format!( "{} {} {}", FILE_HEADER, PHASES, FEATURES );
+        if header.split_whitespace().collect::<Vec<_>>().join( " " ) != expected_header
+        {
+            return Err( invalid_data( format!( "Expected header '{}', got '{}'", expected_header, header ) ) );
+        }
+
+        let mut weights = Weights::zero();
+        for ( line_number, line ) in lines
+        {
+            let line = line?;
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let error = || invalid_data( format!( "Line {}: expected '<phase> <feature> <weight>', got '{}'", line_number + 1, line ) );
+            if fields.len() != 3
+            {
+                return Err( error() );
+            }
+            let phase = fields[0].parse::<usize>().map_err( |_| error() )?;
+            let feature = fields[1].parse::<usize>().map_err( |_| error() )?;
+            let weight = fields[2].parse::<f64>().map_err( |_| error() )?;
+            if phase >= PHASES || feature >= FEATURES || !weight.is_finite()
+            {
+                return Err( error() );
+            }
+            weights.set( phase, feature, weight );
+        }
+        Ok( weights )
+    }
+
+    pub fn write_to<W: Write>( &self, mut writer: W ) -> io::Result<()>
+    {
+        writeln!( writer, "{} {} {}", FILE_HEADER, PHASES, FEATURES )?;
+        for ( phase, weights ) in self.phases.iter().enumerate()
+        {
+            for ( feature, &weight ) in weights.iter().enumerate().filter( |&( _, &weight )| weight != 0.0 )
+            {
+                writeln!( writer, "{} {} {}", phase, feature, weight )?;
+            }
+        }
+        Ok( () )
+    }
+
+    pub fn load<P: AsRef<Path>>( path: P ) -> io::Result<Weights>
+    {
+        Weights::read_from( BufReader::new( File::open( path )? ) )
+    }
+
+    pub fn save<P: AsRef<Path>>( &self, path: P ) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new( File::create( path )? );
+        self.write_to( &mut writer )?;
+        writer.flush()
+    }
+}
+
+//Hand picked weights, in hundredths of a disc
+impl Default for Weights
+{
+    fn default() -> Weights
+    {
+        let by_phase = [ ( MOBILITY, [ 60.0, 50.0, 40.0, 20.0 ] ),
+                         ( POTENTIAL_MOBILITY, [ 20.0, 20.0, 10.0, 5.0 ] ),
+                         ( FRONTIER, [ -30.0, -25.0, -15.0, -5.0 ] ),
+                         ( CORNERS, [ 500.0, 400.0, 300.0, 150.0 ] ),
+                         ( X_SQUARES, [ -250.0, -200.0, -100.0, -20.0 ] ),
+                         ( C_SQUARES, [ -100.0, -80.0, -50.0, -10.0 ] ),
+                         ( STABLE, [ 80.0, 80.0, 70.0, 50.0 ] ),
+                         ( DISCS, [ -5.0, 0.0, 10.0, 60.0 ] ) ];
+        let mut weights = Weights::zero();
+        for &( feature, values ) in by_phase.iter()
+        {
+            for ( phase, &value ) in values.iter().enumerate()
+            {
+                weights.set( phase, feature, value );
+            }
+        }
+        weights
+    }
+}
+
+#[derive(Clone)]
+pub struct PatternOthelloEvaluator
+{
+    weights: Weights
+}
+
+impl PatternOthelloEvaluator
+{
+    pub fn new() -> PatternOthelloEvaluator
+    {
+        Self::with_weights( Weights::default() )
+    }
+
+    pub fn with_weights( weights: Weights ) -> PatternOthelloEvaluator
+    {
+        PatternOthelloEvaluator{ weights }
+    }
+
+    pub fn from_file<P: AsRef<Path>>( path: P ) -> io::Result<PatternOthelloEvaluator>
+    {
+        Ok( Self::with_weights( Weights::load( path )? ) )
+    }
+
+    pub fn weights( &self ) -> &Weights
+    {
+        &self.weights
+    }
+}
+
+impl Default for PatternOthelloEvaluator
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl SituationEvaluator for PatternOthelloEvaluator
+{
+    type Situation = OthelloSituation;
+    const MAX_SCORE: i32 = 6500;
+    fn evaluate_situation( &self, situation: &OthelloSituation ) -> i32
+    {
+        if situation.is_finished()
+        {
+            let score = situation.get_score( situation.get_turn() );
+            return score.signum() * Self::MAX_SCORE;
+        }
+        let ( scalars, edges ) = compute_features( situation );
+        let weights = &self.weights.phases[ phase( situation ) ];
+        let mut value = scalars.iter().zip( weights.iter() ).map( |( feature, weight )| feature * weight ).sum::<f64>();
+        value += edges.iter().map( |&edge| weights[ EDGE_PATTERNS + edge ] ).sum::<f64>();
+        let limit = ( Self::MAX_SCORE - 1 ) as f64;
+        value.round().clamp( -limit, limit ) as i32
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use test::Bencher;
+    use super::*;
+    use othello::OthelloMove;
+
+    fn board( squares: &[&str] ) -> BitBoard
+    {
+        squares.iter().fold( BitBoard::empty(), |board, square| board.with_one_at( Coord::from_str( square ).unwrap() ) )
+    }
+
+    #[test]
+    fn start_position_is_balanced()
+    {
+        let situation = OthelloSituation::new();
+        assert_eq!( PatternOthelloEvaluator::new().evaluate_situation( &situation ), 0 );
+        assert_eq!( phase( &situation ), 0 );
+        //The only features are the four empty edges
+        assert_eq!( features( &situation ), vec![ ( EDGE_PATTERNS, 4.0 ) ] );
+    }
+
+    #[test]
+    fn stable_discs_grow_from_corners()
+    {
+        let own = board( &[ "a1", "b1", "c1", "a2", "e4" ] );
+        let opponent = board( &[ "d1", "b2" ] );
+        assert_eq!( stable_discs( own, opponent ), board( &[ "a1", "b1", "c1", "a2" ] ) );
+        assert_eq!( stable_discs( opponent, own ), BitBoard::empty() );
+    }
+
+    #[test]
+    fn corner_features()
+    {
+        let situation = [ "f5", "d6", "c3", "d3", "c4" ].iter()
+            .fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( a_move.parse::<OthelloMove>().unwrap() ).unwrap() );
+        let ( scalars, edges ) = compute_features( &situation );
+        assert_eq!( scalars[CORNERS], 0.0 );
+        assert_eq!( scalars[DISCS], ( situation.get_own_board().count_ones() - situation.get_opponent_board().count_ones() ) as f64 );
+        assert_eq!( edges, [ 0, 0, 0, 0 ] );
+
+        let own = board( &[ "a1", "b2", "h8" ] );
+        assert_eq!( edge_configuration( own, board( &[ "b1" ] ), &EDGES[0] ), 1 + 2 * 3 );
+    }
+
+    #[test]
+    fn weights_round_trip()
+    {
+        let mut weights = Weights::default();
+        weights.set( 2, EDGE_PATTERNS + 100, -12.5 );
+        let mut file = Vec::new();
+        weights.write_to( &mut file ).unwrap();
+        assert_eq!( Weights::read_from( &file[..] ).unwrap(), weights );
+    }
+
+    #[test]
+    fn malformed_weights_are_rejected()
+    {
+        let header = format!( "{} {} {}\n", FILE_HEADER, PHASES, FEATURES );
+        assert!( Weights::read_from( "".as_bytes() ).is_err() );
+        assert!( Weights::read_from( "bit_board-weights 2 8\n".as_bytes() ).is_err() );
+        assert!( Weights::read_from( ( header.clone() + "0 1\n" ).as_bytes() ).is_err() );
+        assert!( Weights::read_from( ( header.clone() + "9 1 1.0\n" ).as_bytes() ).is_err() );
+        assert!( Weights::read_from( ( header.clone() + "0 1 x\n" ).as_bytes() ).is_err() );
+        let weights = Weights::read_from( ( header + "# comment\n\n1 3 2.5\n" ).as_bytes() ).unwrap();
+        assert_eq!( weights.get( 1, CORNERS ), 2.5 );
+    }
+
+    #[bench]
+    fn bench_evaluate(b: &mut Bencher) {
+        let situation = [ "f5", "d6", "c3", "d3", "c4", "f4", "f6", "f3", "e6", "e7" ].iter()
+            .fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( a_move.parse::<OthelloMove>().unwrap() ).unwrap() );
+        let evaluator = PatternOthelloEvaluator::new();
+        b.iter(|| evaluator.evaluate_situation( &situation ));
+    }
+}
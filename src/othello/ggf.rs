@@ -0,0 +1,494 @@
+//Reading and writing games in GGF, the format of the Generic Game Server and
+//many game archives. A game looks like
+//(;GM[Othello]PC[GGS/os]PB[a]PW[b]RB[1800.5]RW[1750]TI[05:00]RE[+4.000]
+//BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]
+//B[d3//1.02]W[c3/-1.50/0.84]...;)
+//with the start position in BO (* black, O white) and the moves in B and W
+//as square/evaluation/seconds. PA is a pass.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use super::{OthelloSituation, OthelloMove, Player};
+use super::notation::PositionError;
+use super::record::{GameRecord, GameResult, RecordError};
+use super::super::bitboard::{BitBoard, Coord};
+use super::super::game::GameSituation;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GgfError
+{
+    //Unexpected text at the given character
+    Syntax( usize ),
+    //A property with a value that cannot be read, or GM other than Othello
+    Property( String, String ),
+    Board( PositionError ),
+    //The move with the given index is illegal or by the wrong player
+    IllegalMove( usize, OthelloMove )
+}
+
+impl fmt::Display for GgfError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            GgfError::Syntax( position ) => write!(f, "syntax error at character {}", position),
+            GgfError::Property( ref name, ref value ) => write!(f, "invalid value {}[{}]", name, value),
+            GgfError::Board( ref error ) => write!(f, "invalid board: {}", error),
+            GgfError::IllegalMove( index, ref a_move ) => write!(f, "move {} ({}) is illegal", index + 1, a_move)
+        }
+    }
+}
+
+impl error::Error for GgfError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgfMove
+{
+    pub player: Player,
+    pub the_move: OthelloMove,
+    //From the point of view of the player
+    pub eval: Option<f64>,
+    //Seconds used
+    pub time: Option<f64>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgfResult
+{
+    //Black discs minus white discs
+    pub score: f64,
+    //r resigned, t lost on time, s mutual score; None for a game played out
+    pub kind: Option<char>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GgfGame
+{
+    pub place: Option<String>,
+    pub date: Option<String>,
+    pub black: Option<String>,
+    pub white: Option<String>,
+    pub black_rating: Option<f64>,
+    pub white_rating: Option<f64>,
+    //Clocks as written, like 15:00//02:00. TB and TW are per player
+    pub time: Option<String>,
+    pub black_time: Option<String>,
+    pub white_time: Option<String>,
+    pub result: Option<GgfResult>,
+    pub start: OthelloSituation,
+    pub moves: Vec<GgfMove>,
+    //Any other properties, in order
+    pub other: Vec<( String, String )>
+}
+
+impl GgfGame
+{
+    pub fn new() -> GgfGame
+    {
+        GgfGame{ place: None, date: None, black: None, white: None, black_rating: None, white_rating: None,
+                 time: None, black_time: None, white_time: None, result: None,
+                 start: OthelloSituation::new(), moves: Vec::new(), other: Vec::new() }
+    }
+
+    //Takes the start position, moves and result of the record. Forfeits are
+    //written as resignations by 64 discs
+    pub fn from_record( record: &GameRecord ) -> Result<GgfGame, GgfError>
+    {
+        let situations = record.replay().map_err( |error| match error
+        {
+            RecordError::IllegalMove( index, a_move ) => GgfError::IllegalMove( index, a_move ),
+            RecordError::Syntax( position ) => GgfError::Syntax( position )
+        })?;
+        let mut game = GgfGame::new();
+        game.start = record.start().clone();
+        game.moves = record.moves().iter().zip( situations.iter() )
+            .map( |( a_move, situation )| GgfMove{ player: situation.get_turn(), the_move: a_move.clone(), eval: None, time: None } )
+            .collect();
+        game.result = record.result().map( |result| match *result
+        {
+            GameResult::Discs( score ) => GgfResult{ score: score as f64, kind: None },
+            GameResult::Forfeit( Player::Black ) => GgfResult{ score: 64.0, kind: Some( 'r' ) },
+            GameResult::Forfeit( Player::White ) => GgfResult{ score: -64.0, kind: Some( 'r' ) }
+        });
+        Ok( game )
+    }
+
+    //Checks the moves by replaying them
+    pub fn to_record( &self ) -> Result<GameRecord, GgfError>
+    {
+        let mut record = GameRecord::with_start( self.start.clone() );
+        let mut situation = self.start.clone();
+        for ( index, a_move ) in self.moves.iter().enumerate()
+        {
+            let illegal = || GgfError::IllegalMove( index, a_move.the_move.clone() );
+            if a_move.player != situation.get_turn()
+            {
+                return Err( illegal() );
+            }
+            situation = situation.copy_apply( a_move.the_move.clone() ).ok_or_else( illegal )?;
+            record.push( a_move.the_move.clone() );
+        }
+        if let Some( ref result ) = self.result
+        {
+            let forfeit = result.kind == Some( 'r' ) || result.kind == Some( 't' );
+            record.set_result( match forfeit
+            {
+                true if result.score > 0.0 => GameResult::Forfeit( Player::Black ),
+                true if result.score < 0.0 => GameResult::Forfeit( Player::White ),
+                _ => GameResult::Discs( result.score.round() as i32 )
+            });
+        }
+        Ok( record )
+    }
+}
+
+impl Default for GgfGame
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+//Reads all the games of a GGF file
+pub fn read_games( text: &str ) -> Result<Vec<GgfGame>, GgfError>
+{
+    let mut parser = Parser{ chars: text.chars().collect(), position: 0 };
+    let mut games = Vec::new();
+    parser.skip_whitespace();
+    while parser.position < parser.chars.len()
+    {
+        games.push( parser.game()? );
+        parser.skip_whitespace();
+    }
+    Ok( games )
+}
+
+struct Parser
+{
+    chars: Vec<char>,
+    position: usize
+}
+
+impl Parser
+{
+    fn skip_whitespace( &mut self )
+    {
+        while self.chars.get( self.position ).is_some_and( |c| c.is_whitespace() )
+        {
+            self.position += 1;
+        }
+    }
+
+    fn expect( &mut self, expected: &str ) -> Result<(), GgfError>
+    {
+        self.skip_whitespace();
+        for c in expected.chars()
+        {
+            if self.chars.get( self.position ) != Some( &c )
+            {
+                return Err( GgfError::Syntax( self.position ) );
+            }
+            self.position += 1;
+        }
+        Ok( () )
+    }
+
+    fn game( &mut self ) -> Result<GgfGame, GgfError>
+    {
+        self.expect( "(;" )?;
+        let mut game = GgfGame::new();
+        loop
+        {
+            self.skip_whitespace();
+            if self.chars.get( self.position ) == Some( &';' )
+            {
+                self.expect( ";)" )?;
+                return Ok( game );
+            }
+            let start = self.position;
+            while self.chars.get( self.position ).is_some_and( |c| c.is_ascii_uppercase() )
+            {
+                self.position += 1;
+            }
+            if start == self.position
+            {
+                return Err( GgfError::Syntax( self.position ) );
+            }
+            let name = self.chars[start..self.position].iter().collect::<String>();
+            self.expect( "[" )?;
+            let value_start = self.position;
+            while self.chars.get( self.position ).is_some_and( |&c| c != ']' )
+            {
+                self.position += 1;
+            }
+            let value = self.chars[value_start..self.position].iter().collect::<String>();
+            self.expect( "]" )?;
+            property( &mut game, name, value )?;
+        }
+    }
+}
+
+fn property( game: &mut GgfGame, name: String, value: String ) -> Result<(), GgfError>
+{
+    let invalid = |name: &str, value: &str| GgfError::Property( name.to_string(), value.to_string() );
+    match name.as_str()
+    {
+        "GM" => if !value.to_lowercase().starts_with( "othello" ) { return Err( invalid( &name, &value ) ) },
+        "PC" => game.place = Some( value ),
+        "DT" => game.date = Some( value ),
+        "PB" => game.black = Some( value ),
+        "PW" => game.white = Some( value ),
+        "RB" => game.black_rating = Some( value.parse().map_err( |_| invalid( &name, &value ) )? ),
+        "RW" => game.white_rating = Some( value.parse().map_err( |_| invalid( &name, &value ) )? ),
+        "TI" => game.time = Some( value ),
+        "TB" => game.black_time = Some( value ),
+        "TW" => game.white_time = Some( value ),
+        "RE" => game.result = Some( parse_result( &value ).ok_or_else( || invalid( &name, &value ) )? ),
+        "BO" => game.start = parse_board( &value )?,
+        "B" | "W" =>
+        {
+            let player = if name == "B" { Player::Black } else { Player::White };
+            game.moves.push( parse_move( player, &value ).ok_or_else( || invalid( &name, &value ) )? );
+        },
+        _ => game.other.push( ( name, value ) )
+    }
+    Ok( () )
+}
+
+fn parse_result( value: &str ) -> Option<GgfResult>
+{
+    let mut parts = value.splitn( 2, ':' );
+    let score = parts.next()?.trim().parse().ok()?;
+    let kind = match parts.next()
+    {
+        None => None,
+        Some( kind ) if kind.len() == 1 => kind.chars().next(),
+        Some( _ ) => return None
+    };
+    Some( GgfResult{ score, kind } )
+}
+
+fn parse_board( value: &str ) -> Result<OthelloSituation, GgfError>
+{
+    let invalid = || GgfError::Property( "BO".to_string(), value.to_string() );
+    let mut parts = value.trim().splitn( 2, char::is_whitespace );
+    if parts.next() != Some( "8" )
+    {
+        return Err( invalid() );
+    }
+    let chars = parts.next().ok_or_else( invalid )?.chars().filter( |c| !c.is_whitespace() ).collect::<Vec<_>>();
+    if chars.len() != 65
+    {
+        return Err( GgfError::Board( PositionError::Length( chars.len() ) ) );
+    }
+    let mut black_board = BitBoard::empty();
+    let mut white_board = BitBoard::empty();
+    for ( idx, &square ) in chars[..64].iter().enumerate()
+    {
+        let coord = Coord::new( idx as u32 / 8, idx as u32 % 8 ).unwrap();
+        match square
+        {
+            '*' => black_board.set_value_at( coord, true ),
+            'O' => white_board.set_value_at( coord, true ),
+            '-' => (),
+            _ => return Err( GgfError::Board( PositionError::Square( coord, square ) ) )
+        }
+    }
+    let turn = match chars[64]
+    {
+        '*' => Player::Black,
+        'O' => Player::White,
+        side => return Err( GgfError::Board( PositionError::SideToMove( side ) ) )
+    };
+    OthelloSituation::from_boards( black_board, white_board, turn ).map_err( GgfError::Board )
+}
+
+//A move as square/evaluation/seconds, the last two optional
+pub fn parse_move( player: Player, value: &str ) -> Option<GgfMove>
+{
+    let mut parts = value.split( '/' );
+    let square = parts.next()?.trim().to_lowercase();
+    let the_move = if square == "pa" || square == "pass" { OthelloMove::Pass } else { OthelloMove::Coord( Coord::from_str( &square )? ) };
+    let mut number = || -> Option<Option<f64>>
+    {
+        match parts.next().map( str::trim )
+        {
+            None | Some( "" ) => Some( None ),
+            Some( number ) => number.parse().ok().map( Some )
+        }
+    };
+    let eval = number()?;
+    let time = number()?;
+    Some( GgfMove{ player, the_move, eval, time } )
+}
+
+impl FromStr for GgfGame
+{
+    type Err = GgfError;
+    fn from_str( s: &str ) -> Result<Self, Self::Err>
+    {
+        let mut games = read_games( s )?;
+        if games.len() != 1
+        {
+            return Err( GgfError::Syntax( 0 ) );
+        }
+        Ok( games.pop().unwrap() )
+    }
+}
+
+impl fmt::Display for GgfGame
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "(;GM[Othello]")?;
+        let texts = [ ( "PC", &self.place ), ( "DT", &self.date ), ( "PB", &self.black ), ( "PW", &self.white ) ];
+        for &( name, value ) in texts.iter()
+        {
+            if let Some( ref value ) = *value
+            {
+                write!(f, "{}[{}]", name, value)?;
+            }
+        }
+        if let Some( rating ) = self.black_rating
+        {
+            write!(f, "RB[{}]", rating)?;
+        }
+        if let Some( rating ) = self.white_rating
+        {
+            write!(f, "RW[{}]", rating)?;
+        }
+        let clocks = [ ( "TI", &self.time ), ( "TB", &self.black_time ), ( "TW", &self.white_time ) ];
+        for &( name, value ) in clocks.iter()
+        {
+            if let Some( ref value ) = *value
+            {
+                write!(f, "{}[{}]", name, value)?;
+            }
+        }
+        for ( name, value ) in self.other.iter()
+        {
+            write!(f, "{}[{}]", name, value)?;
+        }
+        if let Some( ref result ) = self.result
+        {
+            write!(f, "RE[{:+.3}", result.score)?;
+            if let Some( kind ) = result.kind
+            {
+                write!(f, ":{}", kind)?;
+            }
+            write!(f, "]")?;
+        }
+        write!(f, "BO[8")?;
+        for row in 0..8
+        {
+            write!(f, " ")?;
+            for col in 0..8
+            {
+                let coord = Coord::new( row, col ).unwrap();
+                let square = if self.start.black_board.get_value_at( coord ) { '*' } else if self.start.white_board.get_value_at( coord ) { 'O' } else { '-' };
+                write!(f, "{}", square)?;
+            }
+        }
+        write!(f, " {}]", if self.start.turn == Player::Black { '*' } else { 'O' })?;
+        for a_move in self.moves.iter()
+        {
+            write!(f, "{}[{}]", if a_move.player == Player::Black { "B" } else { "W" }, a_move)?;
+        }
+        write!(f, ";)")
+    }
+}
+
+//As square/evaluation/seconds, without the player
+impl fmt::Display for GgfMove
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self.the_move
+        {
+            OthelloMove::Pass => write!(f, "PA")?,
+            OthelloMove::Coord( coord ) => write!(f, "{}", coord.algebraic().to_uppercase())?
+        }
+        match ( self.eval, self.time )
+        {
+            ( None, None ) => Ok( () ),
+            ( Some( eval ), None ) => write!(f, "/{}", eval),
+            ( None, Some( time ) ) => write!(f, "//{}", time),
+            ( Some( eval ), Some( time ) ) => write!(f, "/{}/{}", eval, time)
+        }
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const GAME: &str = "(;GM[Othello]PC[GGS/os]DT[2003.12.15_13:24:03.MST]PB[alpha]PW[beta]RB[2197.35]RW[2256.22]TI[15:00//02:00]TY[8]RE[+2.000:r]\
+                        BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]\
+                        B[d3//0.01]W[c3/-1.50/0.02]B[C4/0.25]W[e3];)";
+
+    #[test]
+    fn reads_properties_and_moves()
+    {
+        let game = GAME.parse::<GgfGame>().unwrap();
+        assert_eq!( game.black, Some( "alpha".to_string() ) );
+        assert_eq!( game.white_rating, Some( 2256.22 ) );
+        assert_eq!( game.time, Some( "15:00//02:00".to_string() ) );
+        assert_eq!( game.result, Some( GgfResult{ score: 2.0, kind: Some( 'r' ) } ) );
+        assert_eq!( game.other, vec![ ( "TY".to_string(), "8".to_string() ) ] );
+        assert_eq!( game.start, OthelloSituation::new() );
+        assert_eq!( game.moves.len(), 4 );
+        assert_eq!( game.moves[1], GgfMove{ player: Player::White, the_move: OthelloMove::from_str( "c3" ).unwrap(), eval: Some( -1.5 ), time: Some( 0.02 ) } );
+        assert_eq!( game.moves[2].eval, Some( 0.25 ) );
+        assert_eq!( game.moves[2].time, None );
+        let record = game.to_record().unwrap();
+        assert_eq!( record.transcript(), "d3c3c4e3" );
+        assert_eq!( record.result(), Some( &GameResult::Forfeit( Player::Black ) ) );
+    }
+
+    #[test]
+    fn games_round_trip()
+    {
+        let game = GAME.parse::<GgfGame>().unwrap();
+        assert_eq!( game.to_string().parse::<GgfGame>(), Ok( game.clone() ) );
+        let games = read_games( &format!( "{}\n{}\n", GAME, game ) ).unwrap();
+        assert_eq!( games, vec![ game.clone(), game ] );
+    }
+
+    #[test]
+    fn records_round_trip()
+    {
+        let mut record = GameRecord::new();
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            let a_move = moves[ ( ply * 5 ) % moves.len() ].clone();
+            situation = situation.copy_apply( a_move.clone() ).unwrap();
+            record.push( a_move );
+            ply += 1;
+        }
+        record.set_result( GameResult::Discs( situation.get_score( Player::Black ) ) );
+        let game = GgfGame::from_record( &record ).unwrap();
+        assert_eq!( game.to_string().parse::<GgfGame>().unwrap().to_record(), Ok( record ) );
+    }
+
+    #[test]
+    fn malformed_games_are_rejected()
+    {
+        assert_eq!( "GM[Othello];)".parse::<GgfGame>(), Err( GgfError::Syntax( 0 ) ) );
+        assert_eq!( "(;GM[Chess];)".parse::<GgfGame>(), Err( GgfError::Property( "GM".to_string(), "Chess".to_string() ) ) );
+        assert_eq!( "(;RB[strong];)".parse::<GgfGame>(), Err( GgfError::Property( "RB".to_string(), "strong".to_string() ) ) );
+        assert_eq!( "(;B[z9];)".parse::<GgfGame>(), Err( GgfError::Property( "B".to_string(), "z9".to_string() ) ) );
+        assert_eq!( "(;PB[unterminated".parse::<GgfGame>(), Err( GgfError::Syntax( 17 ) ) );
+        assert!( matches!( "(;BO[8 --- *];)".parse::<GgfGame>(), Err( GgfError::Board( PositionError::Length( 4 ) ) ) ) );
+        let wrong_player = "(;B[d3]B[c3];)".parse::<GgfGame>().unwrap();
+        assert_eq!( wrong_player.to_record(), Err( GgfError::IllegalMove( 1, OthelloMove::from_str( "c3" ).unwrap() ) ) );
+    }
+}
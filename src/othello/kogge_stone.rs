@@ -0,0 +1,169 @@
+//Move generation and flip computation with Kogge-Stone parallel prefix fills:
+//instead of stepping one square at a time, each direction is filled in three
+//doubling steps (1, 2 and 4 squares), with no branches. Selected with the
+//kogge_stone feature.
+
+use super::super::bitboard::BitBoard;
+
+pub const NOT_A_FILE: u64 = 0xfefe_fefe_fefe_fefe;
+pub const NOT_H_FILE: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+//The eight directions as ( shift, mask ). Positive shifts go left in the
+//bits, negative ones right. The mask clears the squares a shift wraps onto
+const DIRECTIONS: [( i32, u64 ); 8] = [ ( 1, NOT_A_FILE ),      //Right
+                                         ( -7, NOT_A_FILE ),     //UpRight
+                                         ( -8, !0 ),             //Up
+                                         ( -9, NOT_H_FILE ),     //UpLeft
+                                         ( -1, NOT_H_FILE ),     //Left
+                                         ( 7, NOT_H_FILE ),      //DownLeft
+                                         ( 8, !0 ),              //Down
+                                         ( 9, NOT_A_FILE ) ];    //DownRight
+
+#[inline]
+fn shift( bits: u64, amount: i32 ) -> u64
+{
+    if amount > 0 { bits << amount } else { bits >> -amount }
+}
+
+//Extends generator through the propagator squares in the direction, ie. the
+//generator squares and all the propagator squares in unbroken lines from them
+#[inline]
+fn occluded_fill( mut generator: u64, propagator: u64, amount: i32, mask: u64 ) -> u64
+{
+    let mut propagator = propagator & mask;
+    generator |= propagator & shift( generator, amount );
+    propagator &= shift( propagator, amount );
+    generator |= propagator & shift( generator, 2 * amount );
+    propagator &= shift( propagator, 2 * amount );
+    generator | ( propagator & shift( generator, 4 * amount ) )
+}
+
+pub fn generate_moves( own_board: BitBoard, other_board: BitBoard ) -> BitBoard
+{
+    let ( own, other ) = ( own_board.bits(), other_board.bits() );
+    let empty = !( own | other );
+    let mut moves = 0;
+    for &( amount, mask ) in DIRECTIONS.iter()
+    {
+        let lines = occluded_fill( own, other, amount, mask ) & other;
+        moves |= shift( lines, amount ) & mask & empty;
+    }
+    BitBoard::from_bits( moves )
+}
+
+pub fn delta_for_move( own_board: BitBoard, other_board: BitBoard, move_board: BitBoard ) -> BitBoard
+{
+    let ( own, other, placed ) = ( own_board.bits(), other_board.bits(), move_board.bits() );
+    let mut flips = 0;
+    for &( amount, mask ) in DIRECTIONS.iter()
+    {
+        let line = occluded_fill( placed, other, amount, mask ) & other;
+        //The line is flipped only if it ends in an own disc
+        let closed = shift( line | placed, amount ) & mask & own;
+        flips |= line & 0u64.wrapping_sub( ( closed != 0 ) as u64 );
+    }
+    //Occupied squares are not moves
+    BitBoard::from_bits( flips & 0u64.wrapping_sub( ( placed & ( own | other ) == 0 ) as u64 ) )
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use test::Bencher;
+    use super::*;
+    use super::super::{delta_for_move_stepwise, generate_moves_stepwise};
+    use bitboard::Coord;
+    use ai::Random;
+
+    //Random disjoint boards of varying density
+    fn random_boards( random: &mut Random ) -> ( BitBoard, BitBoard )
+    {
+        let mut occupied = random.next_u64();
+        for _ in 0..random.below( 3 )
+        {
+            occupied |= random.next_u64();
+        }
+        let colors = random.next_u64();
+        ( BitBoard::from_bits( occupied & colors ), BitBoard::from_bits( occupied & !colors ) )
+    }
+
+    #[test]
+    fn matches_stepwise_on_random_boards()
+    {
+        let mut random = Random::new( 12 );
+        for _ in 0..2000
+        {
+            let ( own, other ) = random_boards( &mut random );
+            assert_eq!( generate_moves( own, other ), generate_moves_stepwise( own, other ) );
+            for idx in 0..64
+            {
+                let coord = Coord::new( idx / 8, idx % 8 ).unwrap();
+                let move_board = BitBoard::empty().with_one_at( coord );
+                assert_eq!( delta_for_move( own, other, move_board ), delta_for_move_stepwise( own, other, move_board ) );
+            }
+        }
+    }
+
+    //Perft on bare bitboards, so that the fills get checked whichever
+    //implementation the situation uses
+    fn perft( own: BitBoard, other: BitBoard, depth: u32, passed: bool ) -> u64
+    {
+        if depth == 0
+        {
+            return 1;
+        }
+        let moves = generate_moves( own, other );
+        if moves.is_empty()
+        {
+            return if passed { 1 } else { perft( other, own, depth - 1, true ) };
+        }
+        moves.into_iter().map( |coord|
+        {
+            let move_board = BitBoard::empty().with_one_at( coord );
+            let flips = delta_for_move( own, other, move_board );
+            perft( other ^ flips, own | flips | move_board, depth - 1, false )
+        }).sum()
+    }
+
+    #[test]
+    fn perft_matches_published_numbers()
+    {
+        let black = BitBoard::from_bits( 0x0000_0008_1000_0000 );
+        let white = BitBoard::from_bits( 0x0000_0010_0800_0000 );
+        let expected = [ 1, 4, 12, 56, 244, 1396, 8200, 55092, 390216 ];
+        for ( depth, &nodes ) in expected.iter().enumerate()
+        {
+            assert_eq!( perft( black, white, depth as u32, false ), nodes );
+        }
+    }
+
+    #[bench]
+    fn bench_generate_moves_kogge_stone(b: &mut Bencher) {
+        let mut random = Random::new( 1 );
+        let boards = ( 0..64 ).map( |_| random_boards( &mut random ) ).collect::<Vec<_>>();
+        b.iter(|| boards.iter().fold( 0, |sum, &( own, other )| sum ^ generate_moves( own, other ).bits() ));
+    }
+
+    #[bench]
+    fn bench_generate_moves_stepwise(b: &mut Bencher) {
+        let mut random = Random::new( 1 );
+        let boards = ( 0..64 ).map( |_| random_boards( &mut random ) ).collect::<Vec<_>>();
+        b.iter(|| boards.iter().fold( 0, |sum, &( own, other )| sum ^ generate_moves_stepwise( own, other ).bits() ));
+    }
+
+    #[bench]
+    fn bench_delta_for_move_kogge_stone(b: &mut Bencher) {
+        let mut random = Random::new( 1 );
+        let boards = ( 0..64 ).map( |_| random_boards( &mut random ) ).collect::<Vec<_>>();
+        b.iter(|| boards.iter().fold( 0, |sum, &( own, other )| sum ^ delta_for_move( own, other, BitBoard::from_bits( 1 << 27 ) ).bits() ));
+    }
+
+    #[bench]
+    fn bench_delta_for_move_stepwise(b: &mut Bencher) {
+        let mut random = Random::new( 1 );
+        let boards = ( 0..64 ).map( |_| random_boards( &mut random ) ).collect::<Vec<_>>();
+        b.iter(|| boards.iter().fold( 0, |sum, &( own, other )| sum ^ delta_for_move_stepwise( own, other, BitBoard::from_bits( 1 << 27 ) ).bits() ));
+    }
+}
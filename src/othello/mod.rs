@@ -1,12 +1,27 @@
 
 pub mod players;
+pub mod endgame;
+pub mod evaluation;
+pub mod tuning;
+pub mod notation;
+pub mod record;
+pub mod ggf;
+pub mod wthor;
+pub mod book;
+pub mod nboard;
+pub mod server;
+pub mod tournament;
+mod zobrist;
+mod kogge_stone;
+#[cfg(target_arch = "x86_64")]
+mod simd;
 
 use std::fmt;
 use std::str::FromStr;
 use super::game;
-use super::bitboard::{Coord, Direction, BitBoard, BoardIterator};
+use super::bitboard::{Coord, Direction, BitBoard, BoardIterator, Symmetry};
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Clone, Debug, Hash)]
 pub enum Player
 {
     Black,
@@ -25,10 +40,10 @@ impl fmt::Display for Player
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result 
     {  
-        match self 
+        match *self 
         {
-            &Player::Black => write!(f, "Black"),
-            &Player::White => write!(f, "White")
+            Player::Black => write!(f, "Black"),
+            Player::White => write!(f, "White")
         }
     }
 }
@@ -56,6 +71,18 @@ impl FromStr for OthelloMove  {
    }
 }
 
+impl OthelloMove
+{
+    pub fn transform( &self, symmetry: Symmetry ) -> OthelloMove
+    {
+        match *self
+        {
+            OthelloMove::Pass => OthelloMove::Pass,
+            OthelloMove::Coord( coord ) => OthelloMove::Coord( coord.transform( symmetry ) )
+        }
+    }
+}
+
 impl fmt::Display for OthelloMove
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result 
@@ -92,7 +119,7 @@ impl Iterator for OthelloMoveIterator
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub struct OthelloSituation
 {
     black_board: BitBoard,
@@ -101,7 +128,8 @@ pub struct OthelloSituation
     //Because even terminal ones need to know if there are any legal moves
     //But would it be cheaper to return an iterator of child states. ie. apply the moves as you generate?
     //TODO: test
-    turn: Player
+    turn: Player,
+    hash: u64 //Zobrist hash, kept up to date by copy_apply
 }
 
 // move is one-hot bitboard indicating where the player wants to play
@@ -123,8 +151,26 @@ fn apply_move_towards( own_board: BitBoard, other_board: BitBoard, move_board: B
     BitBoard::empty()
 }
 
-//Returns a bitboard containing ones for all the pieces that will be flipped by a move
+//Returns a bitboard containing ones for all the pieces that will be flipped by a move.
+//The kogge_stone feature selects the portable fills, otherwise AVX2 is used
+//when the CPU has it
 fn delta_for_move( own_board: BitBoard, other_board: BitBoard, move_board: BitBoard ) -> BitBoard
+{
+    if cfg!( feature = "kogge_stone" )
+    {
+        return kogge_stone::delta_for_move( own_board, other_board, move_board );
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        if simd::has_avx2()
+        {
+            return BitBoard::from_bits( unsafe { simd::delta_for_move( own_board.bits(), other_board.bits(), move_board.bits() ) } );
+        }
+    }
+    delta_for_move_stepwise( own_board, other_board, move_board )
+}
+
+fn delta_for_move_stepwise( own_board: BitBoard, other_board: BitBoard, move_board: BitBoard ) -> BitBoard
 {
     let empty_spaces = !own_board & !other_board;
     if (empty_spaces & move_board).is_empty()
@@ -157,6 +203,18 @@ fn generate_moves_towards( own_board: BitBoard, other_board: BitBoard, direction
 }
 
 fn generate_moves( own_board: BitBoard, other_board: BitBoard ) -> BitBoard
+{
+    if cfg!( feature = "kogge_stone" )
+    {
+        kogge_stone::generate_moves( own_board, other_board )
+    }
+    else
+    {
+        generate_moves_stepwise( own_board, other_board )
+    }
+}
+
+fn generate_moves_stepwise( own_board: BitBoard, other_board: BitBoard ) -> BitBoard
 {
     generate_moves_towards( own_board, other_board, Direction::Right ) |
     generate_moves_towards( own_board, other_board, Direction::UpRight ) |
@@ -179,7 +237,8 @@ impl OthelloSituation
         white_board.set_value_at(Coord::new(3,3).unwrap(),true);
         white_board.set_value_at(Coord::new(4,4).unwrap(),true);
         let moves = generate_moves( black_board, white_board );
-        OthelloSituation{ black_board, white_board, moves, turn: Player::Black }
+        let hash = zobrist::hash( black_board, white_board, &Player::Black );
+        OthelloSituation{ black_board, white_board, moves, turn: Player::Black, hash }
     }
 
     fn get_own_board( &self ) -> BitBoard
@@ -211,10 +270,50 @@ impl OthelloSituation
         self.moves = generate_moves( self.get_own_board(), self.get_opponent_board() );            
     }
 
+    //The same position mirrored or rotated, with the same side to move
+    pub fn transform( &self, symmetry: Symmetry ) -> OthelloSituation
+    {
+        let black_board = self.black_board.transform( symmetry );
+        let white_board = self.white_board.transform( symmetry );
+        let hash = zobrist::hash( black_board, white_board, &self.turn );
+        OthelloSituation{ black_board, white_board, moves: self.moves.transform( symmetry ), turn: self.turn.clone(), hash }
+    }
+
+    //The representative of the position under the symmetries: the transform
+    //with the smallest ( black, white ) boards, first one on ties. Returns it
+    //with the symmetry that maps this position (and its moves) onto it;
+    //symmetry.inverse() maps the moves back
+    pub fn canonical( &self ) -> ( OthelloSituation, Symmetry )
+    {
+        let symmetry = Symmetry::all().iter().cloned()
+            .min_by_key( |&symmetry| ( self.black_board.transform( symmetry ).bits(), self.white_board.transform( symmetry ).bits() ) )
+            .unwrap();
+        ( self.transform( symmetry ), symmetry )
+    }
+
+    //Equal for positions that are mirror images or rotations of each other
+    pub fn canonical_key( &self ) -> u64
+    {
+        self.canonical().0.hash
+    }
+
     pub fn get_score(&self, player: Player) -> i32
     {
         (self.white_board.count_ones() - self.black_board.count_ones()) * if player == Player::Black {-1} else {1}
     }
+
+    pub fn empty_squares( &self ) -> u32
+    {
+        ( 64 - self.black_board.count_ones() - self.white_board.count_ones() ) as u32
+    }
+}
+
+impl Default for OthelloSituation
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
 }
 
 impl game::GameSituation for OthelloSituation
@@ -250,7 +349,8 @@ impl game::GameSituation for OthelloSituation
                 white_board |= move_as_board;
             }
 
-            let mut new_situation = OthelloSituation{ black_board, white_board, turn: self.turn.opposite(), moves: BitBoard::empty() };
+            let hash = self.hash ^ zobrist::flip_key( delta ) ^ zobrist::square_key( &self.turn, move_coord ) ^ zobrist::SIDE_TO_MOVE;
+            let mut new_situation = OthelloSituation{ black_board, white_board, turn: self.turn.opposite(), moves: BitBoard::empty(), hash };
 
             new_situation.generate_moves();
 
@@ -261,12 +361,14 @@ impl game::GameSituation for OthelloSituation
             //If returning a pass and no moves available, switch side to move
             if self.moves.is_empty()
             {
-                let new_situation = OthelloSituation{ 
+                let mut new_situation = OthelloSituation{ 
                         black_board: self.black_board, 
                         white_board: self.white_board, 
                         turn: self.turn.opposite(), 
-                        moves: BitBoard::empty() 
+                        moves: BitBoard::empty(),
+                        hash: self.hash ^ zobrist::SIDE_TO_MOVE
                     };
+                new_situation.generate_moves();
                 Some( new_situation )  
             }
             else
@@ -326,6 +428,74 @@ impl game::GameSituation for OthelloSituation
             None
         }
     }
+
+    fn hash_key(&self) -> u64
+    {
+        self.hash
+    }
+}
+
+//What OthelloSituation::undo needs to take a move back
+#[derive(Clone, Debug, PartialEq)]
+pub struct OthelloUndo
+{
+    flips: BitBoard,
+    placed: BitBoard,
+    moves: BitBoard,
+    hash: u64
+}
+
+impl game::ReversibleSituation for OthelloSituation
+{
+    type Undo = OthelloUndo;
+
+    fn apply( &mut self, move_to_play: OthelloMove ) -> Option<OthelloUndo>
+    {
+        let mut undo = OthelloUndo{ flips: BitBoard::empty(), placed: BitBoard::empty(), moves: self.moves, hash: self.hash };
+        match move_to_play
+        {
+            OthelloMove::Coord( move_coord ) =>
+            {
+                undo.placed = BitBoard::empty().with_one_at( move_coord );
+                undo.flips = delta_for_move( self.get_own_board(), self.get_opponent_board(), undo.placed );
+                if undo.flips.is_empty()
+                {
+                    return None;
+                }
+                self.black_board ^= undo.flips;
+                self.white_board ^= undo.flips;
+                if self.turn == Player::Black
+                {
+                    self.black_board |= undo.placed;
+                }
+                else
+                {
+                    self.white_board |= undo.placed;
+                }
+                self.hash ^= zobrist::flip_key( undo.flips ) ^ zobrist::square_key( &self.turn, move_coord );
+            },
+            OthelloMove::Pass =>
+            {
+                if !self.moves.is_empty()
+                {
+                    return None;
+                }
+            }
+        }
+        self.hash ^= zobrist::SIDE_TO_MOVE;
+        self.turn = self.turn.opposite();
+        self.generate_moves();
+        Some( undo )
+    }
+
+    fn undo( &mut self, undo: OthelloUndo )
+    {
+        self.black_board = ( self.black_board ^ undo.flips ) & !undo.placed;
+        self.white_board = ( self.white_board ^ undo.flips ) & !undo.placed;
+        self.moves = undo.moves;
+        self.hash = undo.hash;
+        self.turn = self.turn.opposite();
+    }
 }
 
 impl fmt::Display for OthelloSituation
@@ -370,7 +540,7 @@ mod tests
 
     use test::Bencher;
     use super::*;
-    use super::super::game::GameSituation;
+    use super::super::game::{GameSituation, perft};
 
     #[test]
     fn move_from_string()
@@ -413,6 +583,149 @@ mod tests
         situation.copy_apply(OthelloMove::from_str("c3").unwrap()).expect("Second move failed");
     }
 
+    #[test]
+    fn game_continues_after_pass( )
+    {
+        //White has no discs left next to an empty square it could flip from,
+        //black still has moves
+        let mut black_board = BitBoard::empty();
+        let mut white_board = BitBoard::empty();
+        black_board.set_value_at( Coord::from_str("a1").unwrap(), true );
+        white_board.set_value_at( Coord::from_str("b1").unwrap(), true );
+        black_board.set_value_at( Coord::from_str("h8").unwrap(), true );
+        black_board.set_value_at( Coord::from_str("h7").unwrap(), true );
+        let moves = generate_moves( white_board, black_board );
+        let situation = OthelloSituation{ black_board, white_board, moves, turn: Player::White, hash: zobrist::hash( black_board, white_board, &Player::White ) };
+        assert!( !situation.is_finished() );
+        assert_eq!( situation.get_moves().collect::<Vec<_>>(), vec![OthelloMove::Pass] );
+        let after_pass = situation.copy_apply( OthelloMove::Pass ).unwrap();
+        assert_eq!( after_pass.get_moves().collect::<Vec<_>>(), vec![OthelloMove::Coord(Coord::from_str("c1").unwrap())] );
+    }
+
+    #[test]
+    fn incremental_hash_matches_full_hash( )
+    {
+        let mut situation = OthelloSituation::new();
+        while !situation.is_finished()
+        {
+            assert_eq!( situation.hash_key(), zobrist::hash( situation.black_board, situation.white_board, &situation.turn ) );
+            //Play the last legal move to get some variety compared to the dummy player
+            let last_move = situation.get_moves().last().unwrap();
+            situation = situation.copy_apply( last_move ).unwrap();
+        }
+        assert_eq!( situation.hash_key(), zobrist::hash( situation.black_board, situation.white_board, &situation.turn ) );
+    }
+
+    #[test]
+    fn transpositions_have_equal_hashes( )
+    {
+        let play = |moves: &[&str]| moves.iter().fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str(a_move).unwrap() ).unwrap() );
+        let first = play( &["d3", "c3", "c4", "e3"] );
+        let second = play( &["c4", "c3", "d3", "e3"] );
+        assert_eq!( first, second );
+        assert_eq!( first.hash_key(), second.hash_key() );
+        assert!( first.hash_key() != OthelloSituation::new().hash_key() );
+    }
+
+    #[test]
+    fn apply_and_undo_match_copy_apply( )
+    {
+        use super::super::game::ReversibleSituation;
+        let mut situation = OthelloSituation::new();
+        let mut history = Vec::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let before = situation.clone();
+            if !situation.moves.is_empty()
+            {
+                assert!( situation.apply( OthelloMove::Pass ).is_none() );
+                assert_eq!( situation, before );
+            }
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            let a_move = moves[ ( ply * 3 ) % moves.len() ].clone();
+            let expected = situation.copy_apply( a_move.clone() ).unwrap();
+            history.push( ( situation.apply( a_move ).unwrap(), before ) );
+            assert_eq!( situation, expected );
+            ply += 1;
+        }
+        while let Some( ( undo, before ) ) = history.pop()
+        {
+            situation.undo( undo );
+            assert_eq!( situation, before );
+        }
+    }
+
+    #[test]
+    fn transformed_situations_play_like_the_original( )
+    {
+        let situation = ["f5", "d6", "c3", "d3", "c4"].iter()
+            .fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str(a_move).unwrap() ).unwrap() );
+        for symmetry in Symmetry::all()
+        {
+            let transformed = situation.transform( symmetry );
+            assert_eq!( transformed.moves, generate_moves( transformed.get_own_board(), transformed.get_opponent_board() ) );
+            assert_eq!( transformed.hash_key(), zobrist::hash( transformed.black_board, transformed.white_board, &transformed.turn ) );
+            assert_eq!( transformed.transform( symmetry.inverse() ), situation );
+            for a_move in situation.get_moves()
+            {
+                let child = situation.copy_apply( a_move.clone() ).unwrap();
+                assert_eq!( transformed.copy_apply( a_move.transform( symmetry ) ).unwrap(), child.transform( symmetry ) );
+            }
+        }
+        //The start position is symmetric under the diagonal flips and half turn only
+        assert_eq!( OthelloSituation::new().transform( Symmetry::Rotate180 ), OthelloSituation::new() );
+        assert!( OthelloSituation::new().transform( Symmetry::Rotate90 ) != OthelloSituation::new() );
+    }
+
+    #[test]
+    fn symmetric_positions_share_canonical_form( )
+    {
+        let situation = ["f5", "d6", "c3", "d3", "c4"].iter()
+            .fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str(a_move).unwrap() ).unwrap() );
+        let ( canonical, symmetry ) = situation.canonical();
+        assert_eq!( situation.transform( symmetry ), canonical );
+        for other_symmetry in Symmetry::all()
+        {
+            let transformed = situation.transform( other_symmetry );
+            assert_eq!( transformed.canonical().0, canonical );
+            assert_eq!( transformed.canonical_key(), situation.canonical_key() );
+            //A move in the transformed position maps to the canonical one and back
+            let ( _, to_canonical ) = transformed.canonical();
+            for a_move in transformed.get_moves()
+            {
+                let canonical_move = a_move.transform( to_canonical );
+                assert!( canonical.copy_apply( canonical_move.clone() ).is_some() );
+                assert_eq!( canonical_move.transform( to_canonical.inverse() ), a_move );
+            }
+        }
+        //The four first moves are all the same
+        let keys = OthelloSituation::new().get_moves()
+            .map( |a_move| OthelloSituation::new().copy_apply( a_move ).unwrap().canonical_key() )
+            .collect::<Vec<_>>();
+        assert!( keys.iter().all( |&key| key == keys[0] ) );
+        assert!( keys[0] != OthelloSituation::new().canonical_key() );
+    }
+
+    //Published perft numbers for Othello, passes counted as moves
+    const PERFT: [u64; 10] = [ 1, 4, 12, 56, 244, 1396, 8200, 55092, 390216, 3005288 ];
+
+    #[test]
+    fn perft_matches_published_numbers( )
+    {
+        let situation = OthelloSituation::new();
+        for ( depth, &expected ) in PERFT.iter().enumerate()
+        {
+            assert_eq!( perft( &situation, depth as u32 ), expected, "perft {}", depth );
+        }
+    }
+
+    #[bench]
+    fn bench_perft_6(b: &mut Bencher) {
+        let situation = OthelloSituation::new();
+        b.iter(|| perft( &situation, 6 ));
+    }
+
     #[bench]
     fn bench_generate_moves(b: &mut Bencher) {
         let situation = OthelloSituation::new();
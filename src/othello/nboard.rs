@@ -0,0 +1,405 @@
+//The NBoard protocol, spoken by the NBoard GUI and other tools over the
+//standard input and output of the engine. Commands are one per line:
+//  nboard 2            protocol version, answered with set myname
+//  set depth n         search depth
+//  set game ggf        the game so far, in GGF
+//  move square/eval/t  a move played in the game, PA to pass
+//  go                  answered with === square/eval/seconds
+//  hint n              the n best moves as book or search lines
+//  learn               adds the game to the book, answered with learned
+//  ping n              answered with pong n once everything before is done
+//  quit
+//Status messages are sent while thinking; the GUI shows them as they are.
+
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use super::{OthelloSituation, OthelloMove};
+use super::book::OpeningBook;
+use super::ggf::{self, GgfGame, GgfMove};
+use super::players::{OthelloAlphaBetaPlayer, SimpleOthelloEvaluator};
+use super::super::ai;
+use super::super::external::{EngineProtocol, ExternalError};
+use super::super::game::{GameSituation, Player};
+
+const ENGINE_NAME: &str = "bit_board";
+const DEFAULT_DEPTH: u32 = 8;
+//Moves of a learned game that go into the book
+const BOOK_PLIES: usize = 24;
+
+pub struct NBoardEngine<Evaluator = SimpleOthelloEvaluator>
+{
+    player: OthelloAlphaBetaPlayer<Evaluator>,
+    depth: u32,
+    game: GgfGame,
+    situation: OthelloSituation,
+    //Where learned games are saved
+    book_path: Option<PathBuf>
+}
+
+impl NBoardEngine
+{
+    pub fn new() -> NBoardEngine
+    {
+        Self::with_player( OthelloAlphaBetaPlayer::new( DEFAULT_DEPTH ), DEFAULT_DEPTH )
+    }
+}
+
+impl Default for NBoardEngine
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<Evaluator: ai::SituationEvaluator<Situation = OthelloSituation> + Send> NBoardEngine<Evaluator>
+{
+    //The player should search to depth until the GUI sets another one
+    pub fn with_player( player: OthelloAlphaBetaPlayer<Evaluator>, depth: u32 ) -> NBoardEngine<Evaluator>
+    {
+        NBoardEngine{ player, depth, game: GgfGame::new(), situation: OthelloSituation::new(), book_path: None }
+    }
+
+    //Plays from the book at path, which need not exist yet, and saves the
+    //games the GUI asks to learn there
+    pub fn set_book_file<P: AsRef<Path>>( &mut self, path: P ) -> io::Result<()>
+    {
+        let path = path.as_ref().to_path_buf();
+        let book = if path.exists() { OpeningBook::load( &path )? } else { OpeningBook::new() };
+        self.player.set_book( book, 0.0 );
+        self.book_path = Some( path );
+        Ok( () )
+    }
+
+    //Answers the commands of input until quit or the end of the input
+    pub fn run<R: BufRead, W: Write>( &mut self, input: R, mut output: W ) -> io::Result<()>
+    {
+        for line in input.lines()
+        {
+            if !self.command( line?.trim(), &mut output )?
+            {
+                break;
+            }
+            output.flush()?;
+        }
+        Ok( () )
+    }
+
+    //Returns false on quit
+    fn command<W: Write>( &mut self, line: &str, output: &mut W ) -> io::Result<bool>
+    {
+        let mut words = line.splitn( 2, char::is_whitespace );
+        let command = words.next().unwrap_or( "" );
+        let argument = words.next().unwrap_or( "" ).trim();
+        match command
+        {
+            "" => (),
+            "nboard" =>
+            {
+                if argument != "2"
+                {
+                    writeln!( output, "status unsupported protocol version {}", argument )?;
+                }
+                writeln!( output, "set myname {}", ENGINE_NAME )?;
+            },
+            "set" => self.set( argument, output )?,
+            "move" => match ggf::parse_move( self.situation.get_turn(), argument ).and_then( |a_move| self.play( a_move ) )
+            {
+                Some( () ) => (),
+                None => writeln!( output, "status illegal move {}", argument )?
+            },
+            "go" => self.go( output )?,
+            "hint" => match argument.parse::<usize>()
+            {
+                Ok( count ) => self.hint( count, output )?,
+                Err( _ ) => writeln!( output, "status invalid hint count {}", argument )?
+            },
+            "learn" => self.learn( output )?,
+            "ping" => writeln!( output, "pong {}", argument )?,
+            "quit" => return Ok( false ),
+            //The protocol asks to ignore commands that are not understood
+            _ => ()
+        }
+        Ok( true )
+    }
+
+    fn set<W: Write>( &mut self, argument: &str, output: &mut W ) -> io::Result<()>
+    {
+        let mut words = argument.splitn( 2, char::is_whitespace );
+        let name = words.next().unwrap_or( "" );
+        let value = words.next().unwrap_or( "" ).trim();
+        match name
+        {
+            "depth" => match value.parse::<u32>()
+            {
+                Ok( depth ) if ( 1..=60 ).contains( &depth ) =>
+                {
+                    self.depth = depth;
+                    self.player.set_limits( ai::SearchLimits::depth( depth ) );
+                },
+                _ => writeln!( output, "status invalid depth {}", value )?
+            },
+            "game" => match value.parse::<GgfGame>().and_then( |game| Ok( ( game.to_record()?, game ) ) )
+            {
+                Ok( ( record, game ) ) =>
+                {
+                    //to_record has checked the moves
+                    self.situation = record.final_situation().unwrap();
+                    self.game = game;
+                },
+                Err( error ) => writeln!( output, "status invalid game: {}", error )?
+            },
+            //contempt and anything newer are not supported
+            _ => ()
+        }
+        Ok( () )
+    }
+
+    fn play( &mut self, a_move: GgfMove ) -> Option<()>
+    {
+        self.situation = self.situation.copy_apply( a_move.the_move.clone() )?;
+        self.game.moves.push( a_move );
+        Some( () )
+    }
+
+    //The GUI sends the move back with move, so it is not played here
+    fn go<W: Write>( &mut self, output: &mut W ) -> io::Result<()>
+    {
+        writeln!( output, "status thinking" )?;
+        let start = Instant::now();
+        let previous = self.game.moves.last().map( |a_move| a_move.the_move.clone() );
+        match self.player.make_move( &self.situation, previous )
+        {
+            Some( the_move ) =>
+            {
+                let a_move = GgfMove{ player: self.situation.get_turn(), the_move, eval: None, time: Some( ( start.elapsed().as_secs_f64() * 1000.0 ).round() / 1000.0 ) };
+                writeln!( output, "=== {}", a_move )?;
+            },
+            None => writeln!( output, "status no move, the game is over" )?
+        }
+        writeln!( output, "status" )
+    }
+
+    //The book moves of the position first, then if there are fewer of them
+    //than asked for, the other moves scored by a search each
+    fn hint<W: Write>( &mut self, count: usize, output: &mut W ) -> io::Result<()>
+    {
+        if self.situation.is_finished()
+        {
+            return Ok( () );
+        }
+        writeln!( output, "status thinking" )?;
+        let mut book_moves = self.player.book().map( |book| book.lookup( &self.situation ) ).unwrap_or_default();
+        book_moves.sort_by( |a, b| b.score.total_cmp( &a.score ).then( b.visits.cmp( &a.visits ) ) );
+        for book_move in book_moves.iter().take( count )
+        {
+            writeln!( output, "book {} {:.2} {} {}", square( &book_move.the_move ), book_move.score, book_move.visits, self.depth )?;
+        }
+        if count <= book_moves.len()
+        {
+            return writeln!( output, "status" );
+        }
+        let moves = self.situation.get_moves()
+            .filter( |a_move| book_moves.iter().all( |book_move| book_move.the_move != *a_move ) )
+            .collect::<Vec<_>>();
+        let mut scored = Vec::new();
+        for a_move in moves
+        {
+            let next = self.situation.copy_apply( a_move.clone() ).unwrap();
+            scored.push( ( -self.player.evaluate( &next ), a_move ) );
+        }
+        scored.sort_by_key( |&( score, _ )| -score );
+        for ( score, a_move ) in scored.iter().take( count - book_moves.len() )
+        {
+            writeln!( output, "search {} {} 0 {}", square( a_move ), score, self.depth )?;
+        }
+        writeln!( output, "status" )
+    }
+
+    //Answers learned once the game is in the book and the book is saved
+    fn learn<W: Write>( &mut self, output: &mut W ) -> io::Result<()>
+    {
+        let record = match self.game.to_record()
+        {
+            Ok( record ) => record,
+            Err( error ) => return writeln!( output, "status cannot learn: {}", error )
+        };
+        if self.player.book().is_none()
+        {
+            self.player.set_book( OpeningBook::new(), 0.0 );
+        }
+        let book = self.player.book_mut().unwrap();
+        match book.add_record( &record, BOOK_PLIES )
+        {
+            Ok( true ) => (),
+            Ok( false ) => return writeln!( output, "status cannot learn an unfinished game" ),
+            Err( error ) => return writeln!( output, "status cannot learn: {}", error )
+        }
+        if let Some( ref path ) = self.book_path
+        {
+            if let Err( error ) = book.save( path )
+            {
+                return writeln!( output, "status cannot save the book: {}", error );
+            }
+        }
+        writeln!( output, "learned" )
+    }
+}
+
+//The client side, for playing against programs like Edax. Each request sends
+//the whole position as a game without moves, so the program never has to
+//be kept in step
+pub struct NBoardProtocol
+{
+    depth: u32,
+    started: bool
+}
+
+impl NBoardProtocol
+{
+    pub fn new( depth: u32 ) -> NBoardProtocol
+    {
+        NBoardProtocol{ depth, started: false }
+    }
+}
+
+impl EngineProtocol for NBoardProtocol
+{
+    type Situation = OthelloSituation;
+
+    fn request( &mut self, situation: &OthelloSituation, _previous_move: Option<&OthelloMove> ) -> Result<Vec<String>, ExternalError>
+    {
+        let mut lines = Vec::new();
+        if !self.started
+        {
+            lines.push( "nboard 2".to_string() );
+            lines.push( format!( "set depth {}", self.depth ) );
+            self.started = true;
+        }
+        let game = GgfGame{ start: situation.clone(), ..GgfGame::new() };
+        lines.push( format!( "set game {}", game ) );
+        lines.push( "go".to_string() );
+        Ok( lines )
+    }
+
+    fn parse_reply( &mut self, situation: &OthelloSituation, line: &str ) -> Result<Option<OthelloMove>, ExternalError>
+    {
+        let line = line.trim();
+        if !line.starts_with( "===" )
+        {
+            //set myname, status and the like
+            return Ok( None );
+        }
+        ggf::parse_move( situation.get_turn(), &line[3..] ).map( |a_move| Some( a_move.the_move ) )
+            .ok_or_else( || ExternalError::Garbage( line.to_string() ) )
+    }
+}
+
+fn square( a_move: &OthelloMove ) -> String
+{
+    match *a_move
+    {
+        OthelloMove::Pass => "PA".to_string(),
+        OthelloMove::Coord( coord ) => coord.algebraic().to_uppercase()
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn run( engine: &mut NBoardEngine, script: &str ) -> Vec<String>
+    {
+        let mut output = Vec::new();
+        engine.run( script.as_bytes(), &mut output ).unwrap();
+        String::from_utf8( output ).unwrap().lines().map( str::to_string ).collect()
+    }
+
+    const GAME: &str = "(;GM[Othello]PB[human]PW[bit_board]\
+                        BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]\
+                        B[F5]W[D6/-1.0/0.5];)";
+
+    #[test]
+    fn answers_a_session()
+    {
+        let mut engine = NBoardEngine::new();
+        let script = format!( "nboard 2\nset depth 3\nset game {}\nping 1\nmove c3//2.5\ngo\nhint 2\nping 2\nquit\nping 3\n", GAME );
+        let lines = run( &mut engine, &script );
+        assert_eq!( lines[0], "set myname bit_board" );
+        assert_eq!( lines[1], "pong 1" );
+        assert_eq!( lines[2], "status thinking" );
+        assert!( lines[3].starts_with( "=== " ), "{}", lines[3] );
+        let played = ggf::parse_move( engine.situation.get_turn(), &lines[3][4..] ).unwrap();
+        assert!( engine.situation.copy_apply( played.the_move.clone() ).is_some() );
+        assert!( played.time.is_some() );
+        assert_eq!( &lines[4..6], &[ "status", "status thinking" ] );
+        //White to move after f5 d6 c3
+        let hints = lines[6..8].iter().map( |line| line.split_whitespace().collect::<Vec<_>>() ).collect::<Vec<_>>();
+        assert!( hints.iter().all( |hint| hint[0] == "search" && hint[3] == "0" && hint[4] == "3" ) );
+        assert!( hints[0][2].parse::<i32>().unwrap() >= hints[1][2].parse::<i32>().unwrap() );
+        assert_eq!( &lines[8..], &[ "status", "pong 2" ] );
+        assert_eq!( engine.game.moves.len(), 3 );
+        assert_eq!( engine.game.moves[2].time, Some( 2.5 ) );
+    }
+
+    #[test]
+    fn bad_commands_are_reported()
+    {
+        let mut engine = NBoardEngine::new();
+        let lines = run( &mut engine, "nboard 1\nmove a1\nset depth 0\nset game (;GM[Chess];)\nset contempt 0\nanalyze\nhint x\nlearn\n" );
+        assert_eq!( lines, vec![ "status unsupported protocol version 1", "set myname bit_board",
+                                 "status illegal move a1", "status invalid depth 0",
+                                 "status invalid game: invalid value GM[Chess]", "status invalid hint count x",
+                                 "status cannot learn an unfinished game" ] );
+        assert_eq!( engine.situation, OthelloSituation::new() );
+    }
+
+    #[test]
+    fn protocol_sends_the_whole_position()
+    {
+        let situation = OthelloSituation::new().copy_apply( OthelloMove::Coord( ::bitboard::Coord::from_str( "f5" ).unwrap() ) ).unwrap();
+        let mut protocol = NBoardProtocol::new( 5 );
+        let lines = protocol.request( &situation, None ).unwrap();
+        assert_eq!( &lines[..2], &[ "nboard 2", "set depth 5" ] );
+        assert_eq!( lines[3], "go" );
+        //What the engine side reads back
+        let mut engine = NBoardEngine::new();
+        run( &mut engine, &lines[2] );
+        assert_eq!( engine.situation, situation );
+        assert_eq!( protocol.request( &situation, None ).unwrap().len(), 2 );
+
+        assert_eq!( protocol.parse_reply( &situation, "set myname edax" ).unwrap(), None );
+        assert_eq!( protocol.parse_reply( &situation, "=== D6/-1.00/0.2" ).unwrap(), Some( OthelloMove::Coord( ::bitboard::Coord::from_str( "d6" ).unwrap() ) ) );
+        assert!( matches!( protocol.parse_reply( &situation, "=== what" ), Err( ExternalError::Garbage( _ ) ) ) );
+    }
+
+    #[test]
+    fn learned_games_are_played_and_hinted_from_the_book()
+    {
+        //Black resigns after f5 d6, so white's d6 is a winning move
+        let game = GAME.replace( ";)", "RE[-64.000:r];)" );
+        let path = ::std::env::temp_dir().join( format!( "bit_board_nboard_{}.book", ::std::process::id() ) );
+        let _ = ::std::fs::remove_file( &path );
+        let mut engine = NBoardEngine::new();
+        engine.set_book_file( &path ).unwrap();
+        let script = format!( "set game {}\nlearn\nset game (;GM[Othello]B[f5];)\ngo\nhint 1\nhint 3\n", game );
+        let lines = run( &mut engine, &script );
+        assert_eq!( &lines[..7], &[ "learned", "status thinking", lines[2].as_str(), "status", "status thinking", "book D6 64.00 1 8", "status" ] );
+        assert!( lines[2].starts_with( "=== D6//" ) );
+        //The other two moves are filled in by search
+        assert_eq!( &lines[7..9], &[ "status thinking", "book D6 64.00 1 8" ] );
+        let searched = lines[9..11].iter().map( |line| line.split_whitespace().nth( 1 ).unwrap() ).collect::<Vec<_>>();
+        assert!( lines[9..11].iter().all( |line| line.starts_with( "search " ) ) );
+        assert!( searched.contains( &"F4" ) && searched.contains( &"F6" ) );
+        assert_eq!( &lines[11..], &[ "status" ] );
+
+        let mut reloaded = NBoardEngine::new();
+        reloaded.set_book_file( &path ).unwrap();
+        assert_eq!( reloaded.player.book(), engine.player.book() );
+        ::std::fs::remove_file( &path ).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+//Positions as text in the notation of Edax and NBoard: the 64 squares from a1
+//to h8 row by row ( X black, O white, - empty ) followed by the side to move,
+//X, O or - for a finished game. For example the start position is
+//---------------------------OX------XO--------------------------- X
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use super::{OthelloSituation, OthelloMove, Player, generate_moves, zobrist};
+use super::super::bitboard::{BitBoard, Coord};
+use super::super::game::{GameSituation, MoveNotation};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionError
+{
+    //The number of non-whitespace characters, should be 65
+    Length( usize ),
+    //A square that is not X, O or -
+    Square( Coord, char ),
+    SideToMove( char ),
+    //The boards share squares
+    Overlap( BitBoard ),
+    //One of d4, e4, d5 and e5 is empty, which cannot happen in a game
+    EmptyCenter,
+    //The side to move is - but the game is not over
+    NotFinished
+}
+
+impl fmt::Display for PositionError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            PositionError::Length( length ) => write!(f, "expected 64 squares and the side to move, got {} characters", length),
+            PositionError::Square( coord, square ) => write!(f, "invalid square '{}' at {}", square, coord),
+            PositionError::SideToMove( side ) => write!(f, "invalid side to move '{}'", side),
+            PositionError::Overlap( _ ) => write!(f, "black and white discs on the same squares"),
+            PositionError::EmptyCenter => write!(f, "the center squares must be occupied"),
+            PositionError::NotFinished => write!(f, "no side to move but the game is not finished")
+        }
+    }
+}
+
+impl error::Error for PositionError {}
+
+const CENTER: u64 = 0x0000_0018_1800_0000;
+
+impl OthelloSituation
+{
+    //A position with the given discs and side to move
+    pub fn from_boards( black_board: BitBoard, white_board: BitBoard, turn: Player ) -> Result<OthelloSituation, PositionError>
+    {
+        let overlap = black_board & white_board;
+        if !overlap.is_empty()
+        {
+            return Err( PositionError::Overlap( overlap ) );
+        }
+        if ( black_board | white_board ).bits() & CENTER != CENTER
+        {
+            return Err( PositionError::EmptyCenter );
+        }
+        let ( own_board, other_board ) = if turn == Player::Black { ( black_board, white_board ) } else { ( white_board, black_board ) };
+        let moves = generate_moves( own_board, other_board );
+        let hash = zobrist::hash( black_board, white_board, &turn );
+        Ok( OthelloSituation{ black_board, white_board, moves, turn, hash } )
+    }
+
+    //The position in board notation, through Display
+    pub fn notation( &self ) -> Notation<'_>
+    {
+        Notation( self )
+    }
+}
+
+pub struct Notation<'a>( &'a OthelloSituation );
+
+impl<'a> fmt::Display for Notation<'a>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let situation = self.0;
+        for idx in 0..64
+        {
+            let coord = Coord::new( idx / 8, idx % 8 ).unwrap();
+            let square = if situation.black_board.get_value_at( coord ) { 'X' } else if situation.white_board.get_value_at( coord ) { 'O' } else { '-' };
+            write!(f, "{}", square)?;
+        }
+        let side = if situation.is_finished() { '-' } else if situation.turn == Player::Black { 'X' } else { 'O' };
+        write!(f, " {}", side)
+    }
+}
+
+//Also accepts * for black and . for empty. Whitespace is ignored. Finished
+//games are read with black to move
+impl FromStr for OthelloSituation
+{
+    type Err = PositionError;
+    fn from_str( s: &str ) -> Result<Self, Self::Err>
+    {
+        let chars = s.chars().filter( |c| !c.is_whitespace() ).collect::<Vec<_>>();
+        if chars.len() != 65
+        {
+            return Err( PositionError::Length( chars.len() ) );
+        }
+        let mut black_board = BitBoard::empty();
+        let mut white_board = BitBoard::empty();
+        for ( idx, &square ) in chars[..64].iter().enumerate()
+        {
+            let coord = Coord::new( idx as u32 / 8, idx as u32 % 8 ).unwrap();
+            match square
+            {
+                'X' | 'x' | '*' => black_board.set_value_at( coord, true ),
+                'O' | 'o' => white_board.set_value_at( coord, true ),
+                '-' | '.' => (),
+                _ => return Err( PositionError::Square( coord, square ) )
+            }
+        }
+        match chars[64]
+        {
+            'X' | 'x' | '*' => OthelloSituation::from_boards( black_board, white_board, Player::Black ),
+            'O' | 'o' => OthelloSituation::from_boards( black_board, white_board, Player::White ),
+            '-' =>
+            {
+                let situation = OthelloSituation::from_boards( black_board, white_board, Player::Black )?;
+                if situation.is_finished() { Ok( situation ) } else { Err( PositionError::NotFinished ) }
+            },
+            side => Err( PositionError::SideToMove( side ) )
+        }
+    }
+}
+
+//Moves as squares like F5 or PASS, sides as B and W
+impl MoveNotation for OthelloSituation
+{
+    fn parse_move( &self, text: &str ) -> Option<OthelloMove>
+    {
+        let text = text.trim().to_lowercase();
+        match text.as_str()
+        {
+            "pass" | "pa" => Some( OthelloMove::Pass ),
+            _ => Coord::from_str( &text ).filter( |_| text.len() == 2 ).map( OthelloMove::Coord )
+        }
+    }
+
+    fn format_move( &self, the_move: &OthelloMove ) -> String
+    {
+        match *the_move
+        {
+            OthelloMove::Pass => "PASS".to_string(),
+            OthelloMove::Coord( coord ) => coord.algebraic().to_uppercase()
+        }
+    }
+
+    fn parse_role( text: &str ) -> Option<Player>
+    {
+        match text.trim().to_lowercase().as_str()
+        {
+            "b" | "black" => Some( Player::Black ),
+            "w" | "white" => Some( Player::White ),
+            _ => None
+        }
+    }
+
+    fn format_role( role: &Player ) -> String
+    {
+        if *role == Player::Black { "B".to_string() } else { "W".to_string() }
+    }
+
+    fn format_result( &self ) -> String
+    {
+        let score = self.get_score( Player::Black );
+        match score
+        {
+            0 => "0".to_string(),
+            _ if score > 0 => format!( "B+{}", score ),
+            _ => format!( "W+{}", -score )
+        }
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use othello::OthelloMove;
+
+    const START: &str = "---------------------------OX------XO--------------------------- X";
+
+    #[test]
+    fn start_position_round_trips()
+    {
+        assert_eq!( OthelloSituation::new().notation().to_string(), START );
+        assert_eq!( START.parse::<OthelloSituation>(), Ok( OthelloSituation::new() ) );
+    }
+
+    #[test]
+    fn played_positions_round_trip()
+    {
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let text = situation.notation().to_string();
+            assert_eq!( text.parse::<OthelloSituation>(), Ok( situation.clone() ), "{}", text );
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            situation = situation.copy_apply( moves[ ( ply * 5 ) % moves.len() ].clone() ).unwrap();
+            ply += 1;
+        }
+        let text = situation.notation().to_string();
+        assert!( text.ends_with( " -" ) );
+        assert!( text.parse::<OthelloSituation>().unwrap().is_finished() );
+    }
+
+    #[test]
+    fn after_a_move_white_is_to_move()
+    {
+        let situation = OthelloSituation::new().copy_apply( OthelloMove::from_str( "f5" ).unwrap() ).unwrap();
+        assert_eq!( situation.notation().to_string(), "---------------------------OX------XXX-------------------------- O" );
+    }
+
+    #[test]
+    fn invalid_positions_are_rejected()
+    {
+        assert_eq!( "X".parse::<OthelloSituation>(), Err( PositionError::Length( 1 ) ) );
+        assert_eq!( START.replace( "OX", "OZ" ).parse::<OthelloSituation>(), Err( PositionError::Square( Coord::from_str( "e4" ).unwrap(), 'Z' ) ) );
+        assert_eq!( START.replace( " X", " Y" ).parse::<OthelloSituation>(), Err( PositionError::SideToMove( 'Y' ) ) );
+        assert_eq!( START.replace( " X", " -" ).parse::<OthelloSituation>(), Err( PositionError::NotFinished ) );
+        assert_eq!( START.replace( "OX", "-X" ).parse::<OthelloSituation>(), Err( PositionError::EmptyCenter ) );
+        let board = BitBoard::from_bits( CENTER );
+        assert_eq!( OthelloSituation::from_boards( board, board, Player::Black ), Err( PositionError::Overlap( board ) ) );
+    }
+
+    #[test]
+    fn moves_and_sides_as_text()
+    {
+        let situation = OthelloSituation::new();
+        assert_eq!( situation.parse_move( "F5" ), Some( OthelloMove::from_str( "f5" ).unwrap() ) );
+        assert_eq!( situation.parse_move( "pass" ), Some( OthelloMove::Pass ) );
+        assert_eq!( situation.parse_move( "f55" ), None );
+        assert_eq!( situation.format_move( &OthelloMove::from_str( "c4" ).unwrap() ), "C4" );
+        assert_eq!( OthelloSituation::parse_role( "White" ), Some( Player::White ) );
+        assert_eq!( OthelloSituation::format_role( &Player::Black ), "B" );
+        let finished = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXOOOOOOOOOOOOOOOO -".parse::<OthelloSituation>().unwrap();
+        assert_eq!( finished.format_result(), "B+32" );
+    }
+}
@@ -1,6 +1,9 @@
 use std::io;
+use std::time::{Duration, Instant};
 use super::super::othello::OthelloSituation;
 use super::super::othello::OthelloMove;
+use super::super::othello::endgame::{EndgameSolver, EndgameMode};
+use super::super::othello::book::OpeningBook;
 use super::super::game::GameSituation;
 use super::super::{ai, game, bitboard, othello};
 
@@ -40,6 +43,14 @@ impl HumanOthelloPlayer
     }
 }
 
+impl Default for HumanOthelloPlayer
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
 impl game::Player for HumanOthelloPlayer
 {
     type Move = <OthelloSituation as GameSituation>::Move;
@@ -69,6 +80,14 @@ impl DummyOthelloPlayer
     }
 }
 
+impl Default for DummyOthelloPlayer
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
 impl game::Player for DummyOthelloPlayer
 {
     type Move = <OthelloSituation as GameSituation>::Move;
@@ -79,13 +98,15 @@ impl game::Player for DummyOthelloPlayer
     }
 }
 
-struct SimpleOthelloEvaluator{}
+//Evaluates positions by the disc differential
+#[derive(Clone, Default)]
+pub struct SimpleOthelloEvaluator{}
 
 impl ai::SituationEvaluator for SimpleOthelloEvaluator
 {
     type Situation = othello::OthelloSituation; 
     const MAX_SCORE: i32 = 64;
-    fn evaluate_situation( situation: &Self::Situation ) -> i32
+    fn evaluate_situation( &self, situation: &Self::Situation ) -> i32
     {
         let turn = situation.get_turn();
         if situation.is_finished()
@@ -136,39 +157,267 @@ impl game::Player for OthelloMinMaxPlayer
     }
 }
 
-struct OthelloAlphaBeta
+//Default transposition table size in entries
+const TRANSPOSITION_TABLE_SIZE: usize = 1 << 14;
+
+struct OthelloAlphaBeta<Evaluator>
+{
+    evaluator: Evaluator,
+    table: Option<ai::TranspositionTable< OthelloMove >>,
+    control: ai::SearchControl,
+    variant: ai::SearchVariant
+}
+
+impl<Evaluator> OthelloAlphaBeta<Evaluator>
+{
+    fn new( evaluator: Evaluator, table: Option<ai::TranspositionTable< OthelloMove >> ) -> Self
+    {
+        OthelloAlphaBeta{ evaluator, table, control: ai::SearchControl::new(), variant: ai::SearchVariant::AlphaBeta }
+    }
+}
+
+impl<Evaluator: Clone> OthelloAlphaBeta<Evaluator>
 {
+    //Same settings, but a fresh table
+    fn new_helper( &self ) -> Self
+    {
+        let table = self.table.as_ref().map( |table| ai::TranspositionTable::new( table.size() ) );
+        OthelloAlphaBeta{ variant: self.variant, ..Self::new( self.evaluator.clone(), table ) }
+    }
 }
 
-pub struct OthelloAlphaBetaPlayer
+//Default number of empty squares from which on the game is solved exactly
+const ENDGAME_EMPTIES: u32 = 14;
+
+pub struct OthelloAlphaBetaPlayer<Evaluator = SimpleOthelloEvaluator>
 {
-    max_depth: u32,
-    algorithm: OthelloAlphaBeta
+    limits: ai::SearchLimits,
+    algorithm: OthelloAlphaBeta<Evaluator>,
+    //Searches for the extra threads
+    helpers: Vec<OthelloAlphaBeta<Evaluator>>,
+    endgame_empties: u32,
+    endgame: EndgameSolver,
+    book: Option<OpeningBook>,
+    book_variety: f64,
+    random: ai::Random
 }
 
-impl ai::AlphaBeta for OthelloAlphaBeta
+impl<Evaluator: ai::SituationEvaluator<Situation = OthelloSituation>> ai::AlphaBeta for OthelloAlphaBeta<Evaluator>
 {
     type Move = < OthelloSituation as GameSituation >::Move; 
     type Situation = OthelloSituation;
-    type Evaluator = SimpleOthelloEvaluator;
+    type Evaluator = Evaluator;
+
+    fn evaluator( &self ) -> &Evaluator
+    {
+        &self.evaluator
+    }
+
+    fn transposition_table( &mut self ) -> Option<&mut ai::TranspositionTable< OthelloMove >>
+    {
+        self.table.as_mut()
+    }
+
+    fn search_control( &mut self ) -> Option<&mut ai::SearchControl>
+    {
+        Some( &mut self.control )
+    }
+
+    fn search_variant( &self ) -> ai::SearchVariant
+    {
+        self.variant
+    }
+
+    fn search_child( &mut self, situation: &mut OthelloSituation, a_move: &OthelloMove, depth: u32, alpha: i32, beta: i32 ) -> i32
+    {
+        self.search_child_in_place( situation, a_move, depth, alpha, beta )
+    }
 }
 
 impl OthelloAlphaBetaPlayer
 {
     pub fn new( max_depth: u32 ) -> OthelloAlphaBetaPlayer
     {
-        OthelloAlphaBetaPlayer{ max_depth, algorithm: OthelloAlphaBeta{} }
+        Self::with_limits( ai::SearchLimits::depth( max_depth ) )
+    }
+
+    pub fn with_time_limit( time_per_move: Duration ) -> OthelloAlphaBetaPlayer
+    {
+        Self::with_limits( ai::SearchLimits::time( time_per_move ) )
+    }
+
+    pub fn with_limits( limits: ai::SearchLimits ) -> OthelloAlphaBetaPlayer
+    {
+        Self::with_evaluator( limits, SimpleOthelloEvaluator{} )
+    }
+
+    pub fn with_table_size( limits: ai::SearchLimits, table_size: usize ) -> OthelloAlphaBetaPlayer
+    {
+        let table = Some( ai::TranspositionTable::new( table_size ) );
+        Self::from_algorithm( limits, OthelloAlphaBeta::new( SimpleOthelloEvaluator{}, table ) )
+    }
+
+    pub fn without_transposition_table( max_depth: u32 ) -> OthelloAlphaBetaPlayer
+    {
+        let limits = ai::SearchLimits::depth( max_depth );
+        Self::from_algorithm( limits, OthelloAlphaBeta::new( SimpleOthelloEvaluator{}, None ) )
+    }
+}
+
+impl<Evaluator> OthelloAlphaBetaPlayer<Evaluator>
+{
+    fn from_algorithm( limits: ai::SearchLimits, algorithm: OthelloAlphaBeta<Evaluator> ) -> Self
+    {
+        OthelloAlphaBetaPlayer{ limits, algorithm, helpers: Vec::new(), endgame_empties: ENDGAME_EMPTIES, endgame: EndgameSolver::new( EndgameMode::Exact ),
+                                book: None, book_variety: 0.0, random: ai::Random::from_clock() }
     }
 }
 
-impl game::Player for OthelloAlphaBetaPlayer
+impl<Evaluator: ai::SituationEvaluator<Situation = OthelloSituation>> OthelloAlphaBetaPlayer<Evaluator>
+{
+    pub fn with_evaluator( limits: ai::SearchLimits, evaluator: Evaluator ) -> OthelloAlphaBetaPlayer<Evaluator>
+    {
+        let table = Some( ai::TranspositionTable::new( TRANSPOSITION_TABLE_SIZE ) );
+        Self::from_algorithm( limits, OthelloAlphaBeta::new( evaluator, table ) )
+    }
+
+    pub fn set_search_variant( &mut self, variant: ai::SearchVariant )
+    {
+        self.algorithm.variant = variant;
+        for helper in self.helpers.iter_mut()
+        {
+            helper.variant = variant;
+        }
+    }
+
+    //With this many or fewer empty squares the endgame solver is tried
+    //before the search. It gets half of the time and nodes of the limits,
+    //the search what is left if it cannot finish. Zero turns the solver off
+    pub fn set_endgame_empties( &mut self, empties: u32 )
+    {
+        self.endgame_empties = empties;
+    }
+
+    pub fn set_limits( &mut self, limits: ai::SearchLimits )
+    {
+        self.limits = limits;
+    }
+
+    //Positions in the book are played from it without searching. variety
+    //is in discs, see OpeningBook::choose_move; zero always plays the best
+    //book move
+    pub fn set_book( &mut self, book: OpeningBook, variety: f64 )
+    {
+        self.book = Some( book );
+        self.book_variety = variety;
+    }
+
+    pub fn book( &self ) -> Option<&OpeningBook>
+    {
+        self.book.as_ref()
+    }
+
+    pub fn book_mut( &mut self ) -> Option<&mut OpeningBook>
+    {
+        self.book.as_mut()
+    }
+
+    //Seeds the choice among book moves, by default seeded from the clock
+    pub fn set_seed( &mut self, seed: u64 )
+    {
+        self.random = ai::Random::new( seed );
+    }
+
+    //Score of the position for the side to move by a search within the
+    //limits, eg. to build a book with OpeningBook::add_searched
+    pub fn evaluate( &mut self, situation: &OthelloSituation ) -> i32
+    {
+        use ai::AlphaBeta;
+        self.algorithm.search_iterative( situation, self.limits ).1
+    }
+}
+
+impl<Evaluator: ai::SituationEvaluator<Situation = OthelloSituation> + Clone> OthelloAlphaBetaPlayer<Evaluator>
+{
+    //Splits the search at the root over this many threads. With one thread
+    //(the default) the search is deterministic
+    pub fn set_threads( &mut self, threads: usize )
+    {
+        let helpers = threads.max( 1 ) - 1;
+        self.helpers.truncate( helpers );
+        while self.helpers.len() < helpers
+        {
+            let helper = self.algorithm.new_helper();
+            self.helpers.push( helper );
+        }
+    }
+}
+
+impl<Evaluator: ai::SituationEvaluator<Situation = OthelloSituation> + Send> game::Player for OthelloAlphaBetaPlayer<Evaluator>
 {
     type Move = <OthelloSituation as GameSituation>::Move;
     type Situation = OthelloSituation;
     fn make_move( &mut self, situation: &Self::Situation, _previous_move: Option<Self::Move> ) -> Option<Self::Move>
     {
         use ai::AlphaBeta;
-        self.algorithm.search_root( situation, self.max_depth ).0
+        if let Some( ref book ) = self.book
+        {
+            if let Some( a_move ) = book.choose_move( situation, self.book_variety, &mut self.random )
+            {
+                return Some( a_move );
+            }
+        }
+        let mut limits = self.limits;
+        if situation.empty_squares() <= self.endgame_empties
+        {
+            let start = Instant::now();
+            let solver_limits = ai::SearchLimits{ max_depth: None, time: limits.time.map( |time| time / 2 ), nodes: limits.nodes.map( |nodes| nodes / 2 ) };
+            if let Some( ( the_move, _ ) ) = self.endgame.solve( situation, solver_limits )
+            {
+                return the_move;
+            }
+            limits.time = limits.time.map( |time| time.saturating_sub( start.elapsed() ) );
+            limits.nodes = limits.nodes.map( |nodes| nodes.saturating_sub( self.endgame.nodes() ) );
+        }
+        if self.helpers.is_empty()
+        {
+            return self.algorithm.search_iterative( situation, limits ).0;
+        }
+        let mut searches = Some( &mut self.algorithm ).into_iter().chain( self.helpers.iter_mut() ).collect::<Vec<_>>();
+        ai::search_iterative_split( &mut searches, situation, limits ).0
+    }
+}
+
+pub struct OthelloMctsPlayer
+{
+    algorithm: ai::Mcts< OthelloSituation, ai::RandomPlayout >
+}
+
+impl OthelloMctsPlayer
+{
+    pub fn new( iterations: u64 ) -> OthelloMctsPlayer
+    {
+        Self::with_config( ai::MctsConfig::iterations( iterations ) )
+    }
+
+    pub fn with_time_limit( time_per_move: Duration ) -> OthelloMctsPlayer
+    {
+        Self::with_config( ai::MctsConfig::time( time_per_move ) )
+    }
+
+    pub fn with_config( config: ai::MctsConfig ) -> OthelloMctsPlayer
+    {
+        OthelloMctsPlayer{ algorithm: ai::Mcts::new( config, ai::RandomPlayout{} ) }
+    }
+}
+
+impl game::Player for OthelloMctsPlayer
+{
+    type Move = <OthelloSituation as GameSituation>::Move;
+    type Situation = OthelloSituation;
+    fn make_move( &mut self, situation: &Self::Situation, previous_move: Option<Self::Move> ) -> Option<Self::Move>
+    {
+        self.algorithm.search( situation, previous_move )
     }
 }
 
@@ -176,9 +425,220 @@ impl game::Player for OthelloAlphaBetaPlayer
 mod tests
 {
     use test::Bencher;
+    use std::str::FromStr;
     use game::Player;
     use super::*;
 
+    fn test_situation() -> OthelloSituation
+    {
+        OthelloSituation::new()
+            .copy_apply( OthelloMove::from_str("d3").unwrap() ).unwrap()
+            .copy_apply( OthelloMove::from_str("c5").unwrap() ).unwrap()
+    }
+
+    #[test]
+    fn alpha_beta_matches_min_max()
+    {
+        use ai::AlphaBeta;
+        let minmax: ai::MinMax< OthelloMinMaxTraits > = ai::MinMax::new();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 1 );
+        let situation = test_situation();
+        for depth in 1..5
+        {
+            assert_eq!( player.algorithm.search_root( &situation, depth ).1, minmax.search_root( &situation, depth ).1 );
+        }
+    }
+
+    #[test]
+    fn in_place_search_matches_copying()
+    {
+        use ai::AlphaBeta;
+        let minmax: ai::MinMax< OthelloMinMaxTraits > = ai::MinMax::new();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 1 );
+        for mut situation in test_positions()
+        {
+            let original = situation.clone();
+            assert_eq!( minmax.search_root_in_place( &situation, 3 ), minmax.search_root( &situation, 3 ) );
+            let expected = player.algorithm.search( situation.clone(), 4, -64, 64 );
+            assert_eq!( player.algorithm.search_in_place( &mut situation, 4, -64, 64 ), expected );
+            assert_eq!( player.algorithm.search_pv_in_place( &mut situation, 4, -64, 64 ), expected );
+            assert_eq!( situation, original );
+        }
+    }
+
+    //At shallow depths the presort at the root leaves deeper results in the
+    //table than the main search asks for, so only compare from depth 4 on
+    #[test]
+    fn transposition_table_keeps_scores()
+    {
+        use ai::AlphaBeta;
+        let situation = test_situation();
+        for depth in 4..6
+        {
+            let mut with_table = OthelloAlphaBetaPlayer::new( depth );
+            let mut without_table = OthelloAlphaBetaPlayer::without_transposition_table( depth );
+            assert_eq!( with_table.algorithm.search_root( &situation, depth ).1, 
+                        without_table.algorithm.search_root( &situation, depth ).1 );
+        }
+    }
+
+    //Positions from a deterministic game, covering opening to endgame
+    fn test_positions() -> Vec<OthelloSituation>
+    {
+        let mut positions = Vec::new();
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            let next = situation.copy_apply( moves[ ( ply * 7 ) % moves.len() ].clone() ).unwrap();
+            if ply % 6 == 3
+            {
+                positions.push( situation );
+            }
+            situation = next;
+            ply += 1;
+        }
+        positions
+    }
+
+    #[test]
+    fn principal_variation_matches_alpha_beta()
+    {
+        use ai::AlphaBeta;
+        let mut alpha_beta = OthelloAlphaBetaPlayer::without_transposition_table( 1 );
+        let mut principal_variation = OthelloAlphaBetaPlayer::without_transposition_table( 1 );
+        principal_variation.set_search_variant( ai::SearchVariant::PrincipalVariation );
+        for situation in test_positions()
+        {
+            for depth in 1..4
+            {
+                let expected = alpha_beta.algorithm.search_root( &situation, depth ).1;
+                assert_eq!( principal_variation.algorithm.search_root( &situation, depth ).1, expected );
+            }
+        }
+    }
+
+    #[test]
+    fn aspiration_windows_match_alpha_beta()
+    {
+        use ai::AlphaBeta;
+        let mut alpha_beta = OthelloAlphaBetaPlayer::without_transposition_table( 1 );
+        let mut principal_variation = OthelloAlphaBetaPlayer::new( 4 );
+        principal_variation.set_search_variant( ai::SearchVariant::PrincipalVariation );
+        for situation in test_positions()
+        {
+            let expected = alpha_beta.algorithm.search_root( &situation, 4 ).1;
+            assert_eq!( principal_variation.algorithm.search_iterative( &situation, ai::SearchLimits::depth( 4 ) ).1, expected );
+        }
+    }
+
+    #[test]
+    fn iterative_deepening_matches_fixed_depth()
+    {
+        use ai::AlphaBeta;
+        let situation = test_situation();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 4 );
+        let fixed = player.algorithm.search_root( &situation, 4 ).1;
+        assert_eq!( player.algorithm.search_iterative( &situation, ai::SearchLimits::depth( 4 ) ).1, fixed );
+    }
+
+    #[test]
+    fn split_search_matches_single_thread()
+    {
+        use ai::AlphaBeta;
+        for variant in [ ai::SearchVariant::AlphaBeta, ai::SearchVariant::PrincipalVariation ]
+        {
+            let mut single = OthelloAlphaBetaPlayer::without_transposition_table( 4 );
+            let mut split = OthelloAlphaBetaPlayer::new( 4 );
+            split.set_search_variant( variant );
+            split.set_threads( 3 );
+            for situation in test_positions().iter().filter( |situation| !situation.is_finished() )
+            {
+                let expected = single.algorithm.search_root( situation, 4 ).1;
+                let mut searches = Some( &mut split.algorithm ).into_iter().chain( split.helpers.iter_mut() ).collect::<Vec<_>>();
+                let ( best_move, score ) = ai::search_iterative_split( &mut searches, situation, ai::SearchLimits::depth( 4 ) );
+                assert_eq!( score, expected );
+                let child = situation.copy_apply( best_move.unwrap() ).unwrap();
+                assert_eq!( -single.algorithm.search_root( &child, 3 ).1, expected );
+            }
+        }
+    }
+
+    #[test]
+    fn endgame_solver_beats_dummy()
+    {
+        use super::super::super::OthelloGame;
+        let mut player = OthelloAlphaBetaPlayer::new( 1 );
+        player.set_endgame_empties( 10 );
+        let mut game = OthelloGame::new( Box::new( DummyOthelloPlayer::new() ), Box::new( player ) );
+        assert_eq!( game.play(), Some( othello::Player::White ) );
+    }
+
+    #[test]
+    fn pattern_evaluator_beats_dummy()
+    {
+        use super::super::super::OthelloGame;
+        use othello::evaluation::PatternOthelloEvaluator;
+        let player = OthelloAlphaBetaPlayer::with_evaluator( ai::SearchLimits::depth( 2 ), PatternOthelloEvaluator::new() );
+        let mut game = OthelloGame::new( Box::new( player ), Box::new( DummyOthelloPlayer::new() ) );
+        assert_eq!( game.play(), Some( othello::Player::Black ) );
+    }
+
+    #[test]
+    fn limited_players_return_legal_moves()
+    {
+        let situation = test_situation();
+        let mut players = [ OthelloAlphaBetaPlayer::with_time_limit( Duration::from_millis( 20 ) ),
+                                OthelloAlphaBetaPlayer::with_limits( ai::SearchLimits::nodes( 500 ) ),
+                                OthelloAlphaBetaPlayer::with_limits( ai::SearchLimits::nodes( 0 ) ) ];
+        for player in players.iter_mut()
+        {
+            let the_move = player.make_move( &situation, None ).unwrap();
+            assert!( situation.copy_apply( the_move ).is_some() );
+        }
+    }
+
+    #[test]
+    fn limited_solves_fall_back_to_the_search()
+    {
+        use ai::AlphaBeta;
+        let mut situation = test_situation();
+        while situation.empty_squares() > 16
+        {
+            situation = situation.copy_apply( situation.get_moves().next().unwrap() ).unwrap();
+        }
+        let mut player = OthelloAlphaBetaPlayer::with_limits( ai::SearchLimits::nodes( 2000 ) );
+        player.set_endgame_empties( 16 );
+        let the_move = player.make_move( &situation, None );
+        //The solve cannot finish, the search returns what it found in the
+        //nodes left to it
+        assert!( player.endgame.nodes() > 1000 );
+        let mut search = OthelloAlphaBetaPlayer::with_limits( ai::SearchLimits::nodes( 2000 - player.endgame.nodes() ) );
+        assert_eq!( the_move, search.algorithm.search_iterative( &situation, search.limits ).0 );
+    }
+
+    #[test]
+    fn book_moves_are_played_before_searching()
+    {
+        let mut searcher = OthelloAlphaBetaPlayer::new( 3 );
+        let mut book = OpeningBook::new();
+        book.add_searched( &OthelloSituation::new(), 2, 1, |situation| searcher.evaluate( situation ) );
+        let situation = test_situation();
+        let searched = searcher.make_move( &situation, None ).unwrap();
+        let other = situation.get_moves().find( |a_move| *a_move != searched ).unwrap();
+        book.set_score( &situation, &other, 64 );
+
+        let mut player = OthelloAlphaBetaPlayer::new( 3 );
+        player.set_book( book, 0.0 );
+        assert_eq!( player.make_move( &situation, None ), Some( other ) );
+        let start_moves = OthelloSituation::new().get_moves().collect::<Vec<_>>();
+        assert!( start_moves.contains( &player.make_move( &OthelloSituation::new(), None ).unwrap() ) );
+        //Out of the book the search takes over
+        let later = test_positions()[4].clone();
+        assert_eq!( player.make_move( &later, None ), searcher.make_move( &later, None ) );
+    }
+
     #[bench]
     fn bench_min_max_player_d3(b: &mut Bencher) {
         let situation = OthelloSituation::new();
@@ -205,9 +665,83 @@ mod tests
         b.iter(|| player.make_move(&situation, None));
     }
     #[bench]
+    fn bench_mcts_player_1000(b: &mut Bencher) {
+        let situation = OthelloSituation::new();
+        let mut player = OthelloMctsPlayer::with_config( ai::MctsConfig{ seed: Some( 1 ), ..ai::MctsConfig::iterations( 1000 ) } );
+        b.iter(|| player.make_move(&situation, None));
+    }
+    #[bench]
+    fn bench_min_max_d5_copy(b: &mut Bencher) {
+        let situation = OthelloSituation::new();
+        let minmax: ai::MinMax< OthelloMinMaxTraits > = ai::MinMax::new();
+        b.iter(|| minmax.search_root( &situation, 5 ));
+    }
+    #[bench]
+    fn bench_min_max_d5_in_place(b: &mut Bencher) {
+        let situation = OthelloSituation::new();
+        let minmax: ai::MinMax< OthelloMinMaxTraits > = ai::MinMax::new();
+        b.iter(|| minmax.search_root_in_place( &situation, 5 ));
+    }
+    //The table is recreated every iteration, otherwise all but the first
+    //search would be answered straight from the table
+    #[bench]
     fn bench_ab_player_d7(b: &mut Bencher) {
         let situation = OthelloSituation::new();
-        let mut player = OthelloAlphaBetaPlayer::new( 7 );
+        b.iter(|| OthelloAlphaBetaPlayer::new( 7 ).make_move(&situation, None));
+    }
+    #[bench]
+    fn bench_ab_player_d7_no_table(b: &mut Bencher) {
+        let situation = OthelloSituation::new();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 7 );
         b.iter(|| player.make_move(&situation, None));
     }
-}
\ No newline at end of file
+
+    fn midgame_situation() -> OthelloSituation
+    {
+        ["f5", "d6", "c3", "d3", "c4", "f4", "f6", "f3", "e6", "e7"].iter()
+            .fold( OthelloSituation::new(), |situation, a_move| situation.copy_apply( OthelloMove::from_str(a_move).unwrap() ).unwrap() )
+    }
+    #[bench]
+    fn bench_ab_player_midgame_d7(b: &mut Bencher) {
+        let situation = midgame_situation();
+        b.iter(|| OthelloAlphaBetaPlayer::new( 7 ).make_move(&situation, None));
+    }
+    #[bench]
+    fn bench_pvs_player_midgame_d7(b: &mut Bencher) {
+        let situation = midgame_situation();
+        b.iter(|| {
+            let mut player = OthelloAlphaBetaPlayer::new( 7 );
+            player.set_search_variant( ai::SearchVariant::PrincipalVariation );
+            player.make_move(&situation, None)
+        });
+    }
+    #[bench]
+    fn bench_ab_player_midgame_d7_4_threads(b: &mut Bencher) {
+        let situation = midgame_situation();
+        b.iter(|| {
+            let mut player = OthelloAlphaBetaPlayer::new( 7 );
+            player.set_threads( 4 );
+            player.make_move(&situation, None)
+        });
+    }
+    #[bench]
+    fn bench_ab_midgame_d7_copy(b: &mut Bencher) {
+        use ai::AlphaBeta;
+        let situation = midgame_situation();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 7 );
+        b.iter(|| player.algorithm.search( situation.clone(), 7, -64, 64 ));
+    }
+    #[bench]
+    fn bench_ab_midgame_d7_in_place(b: &mut Bencher) {
+        use ai::AlphaBeta;
+        let mut situation = midgame_situation();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 7 );
+        b.iter(|| player.algorithm.search_in_place( &mut situation, 7, -64, 64 ));
+    }
+    #[bench]
+    fn bench_ab_player_midgame_d7_no_table(b: &mut Bencher) {
+        let situation = midgame_situation();
+        let mut player = OthelloAlphaBetaPlayer::without_transposition_table( 7 );
+        b.iter(|| player.make_move(&situation, None));
+    }
+}
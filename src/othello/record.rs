@@ -0,0 +1,255 @@
+//Game records: the start position, the moves and the result of a game. As
+//text they use the compact transcript format, the squares of the moves one
+//after the other (f5d6c3d3...) with the passes left out.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+use super::{OthelloSituation, OthelloMove, Player};
+use super::super::bitboard::Coord;
+use super::super::game::GameSituation;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameResult
+{
+    //The game was played to the end, black discs minus white discs
+    Discs( i32 ),
+    //The other player gave up or played an illegal move; holds the winner
+    Forfeit( Player )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordError
+{
+    //A transcript that is not a list of squares, at the given character
+    Syntax( usize ),
+    //The move with the given index is not legal
+    IllegalMove( usize, OthelloMove )
+}
+
+impl fmt::Display for RecordError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            RecordError::Syntax( position ) => write!(f, "invalid square at character {}", position),
+            RecordError::IllegalMove( index, ref a_move ) => write!(f, "move {} ({}) is illegal", index + 1, a_move)
+        }
+    }
+}
+
+impl error::Error for RecordError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord
+{
+    start: OthelloSituation,
+    //Including the passes
+    moves: Vec<OthelloMove>,
+    result: Option<GameResult>
+}
+
+impl GameRecord
+{
+    pub fn new() -> GameRecord
+    {
+        Self::with_start( OthelloSituation::new() )
+    }
+
+    pub fn with_start( start: OthelloSituation ) -> GameRecord
+    {
+        GameRecord{ start, moves: Vec::new(), result: None }
+    }
+
+    pub fn start( &self ) -> &OthelloSituation
+    {
+        &self.start
+    }
+
+    pub fn moves( &self ) -> &[OthelloMove]
+    {
+        &self.moves
+    }
+
+    //None while the game is in progress
+    pub fn result( &self ) -> Option<&GameResult>
+    {
+        self.result.as_ref()
+    }
+
+    pub fn set_result( &mut self, result: GameResult )
+    {
+        self.result = Some( result );
+    }
+
+    //Adds a move without checking it, see replay
+    pub fn push( &mut self, a_move: OthelloMove )
+    {
+        self.moves.push( a_move );
+    }
+
+    //All the positions of the game from the start, each move checked with
+    //copy_apply
+    pub fn replay( &self ) -> Result<Vec<OthelloSituation>, RecordError>
+    {
+        let mut situations = vec![ self.start.clone() ];
+        for ( index, a_move ) in self.moves.iter().enumerate()
+        {
+            let next = situations.last().unwrap().copy_apply( a_move.clone() )
+                .ok_or_else( || RecordError::IllegalMove( index, a_move.clone() ) )?;
+            situations.push( next );
+        }
+        Ok( situations )
+    }
+
+    pub fn final_situation( &self ) -> Result<OthelloSituation, RecordError>
+    {
+        Ok( self.replay()?.pop().unwrap() )
+    }
+
+    //Reads a transcript played from start. Passes are added where the side
+    //to move has no moves, and the result is set if the game is over
+    pub fn from_transcript( start: OthelloSituation, transcript: &str ) -> Result<GameRecord, RecordError>
+    {
+        let mut record = GameRecord::with_start( start );
+        let mut situation = record.start.clone();
+        let chars = transcript.chars().collect::<Vec<_>>();
+        let mut position = 0;
+        while position < chars.len()
+        {
+            if chars[position].is_whitespace()
+            {
+                position += 1;
+                continue;
+            }
+            let square = chars[position..].iter().take( 2 ).collect::<String>().to_lowercase();
+            let coord = Coord::from_str( &square ).filter( |_| square.len() == 2 ).ok_or( RecordError::Syntax( position ) )?;
+            position += 2;
+            if !situation.is_finished() && situation.get_moves().next() == Some( OthelloMove::Pass )
+            {
+                situation = situation.copy_apply( OthelloMove::Pass ).unwrap();
+                record.moves.push( OthelloMove::Pass );
+            }
+            let a_move = OthelloMove::Coord( coord );
+            situation = situation.copy_apply( a_move.clone() ).ok_or_else( || RecordError::IllegalMove( record.moves.len(), a_move.clone() ) )?;
+            record.moves.push( a_move );
+        }
+        if situation.is_finished()
+        {
+            record.result = Some( GameResult::Discs( situation.get_score( Player::Black ) ) );
+        }
+        Ok( record )
+    }
+
+    //The moves without the passes
+    pub fn transcript( &self ) -> String
+    {
+        self.moves.iter().filter_map( |a_move| match *a_move
+        {
+            OthelloMove::Pass => None,
+            OthelloMove::Coord( coord ) => Some( coord.algebraic() )
+        }).collect()
+    }
+}
+
+impl Default for GameRecord
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl fmt::Display for GameRecord
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{}", self.transcript())
+    }
+}
+
+//A transcript from the start position
+impl FromStr for GameRecord
+{
+    type Err = RecordError;
+    fn from_str( s: &str ) -> Result<Self, Self::Err>
+    {
+        GameRecord::from_transcript( OthelloSituation::new(), s )
+    }
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //A whole game with the moves chosen by a simple rule, passes included
+    fn played_record() -> GameRecord
+    {
+        played_record_with( 3 )
+    }
+
+    fn played_record_with( step: usize ) -> GameRecord
+    {
+        let mut record = GameRecord::new();
+        let mut situation = OthelloSituation::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let moves = situation.get_moves().collect::<Vec<_>>();
+            let a_move = moves[ ( ply * step ) % moves.len() ].clone();
+            situation = situation.copy_apply( a_move.clone() ).unwrap();
+            record.push( a_move );
+            ply += 1;
+        }
+        record.set_result( GameResult::Discs( situation.get_score( Player::Black ) ) );
+        record
+    }
+
+    #[test]
+    fn transcript_round_trips()
+    {
+        let record = played_record();
+        let transcript = record.to_string();
+        assert_eq!( transcript.len(), 2 * record.moves().iter().filter( |a_move| **a_move != OthelloMove::Pass ).count() );
+        assert_eq!( transcript.parse::<GameRecord>(), Ok( record ) );
+    }
+
+    #[test]
+    fn passes_are_implicit()
+    {
+        let with_passes = ( 1..20 ).map( played_record_with )
+            .filter( |record| record.moves().contains( &OthelloMove::Pass ) )
+            .collect::<Vec<_>>();
+        assert!( !with_passes.is_empty() );
+        for record in with_passes
+        {
+            assert!( !record.transcript().contains( "pass" ) );
+            assert_eq!( record.transcript().parse::<GameRecord>().unwrap().moves(), record.moves() );
+        }
+    }
+
+    #[test]
+    fn replay_checks_moves()
+    {
+        let mut record = GameRecord::new();
+        record.push( OthelloMove::from_str( "f5" ).unwrap() );
+        record.push( OthelloMove::from_str( "f5" ).unwrap() );
+        assert_eq!( record.replay(), Err( RecordError::IllegalMove( 1, OthelloMove::from_str( "f5" ).unwrap() ) ) );
+        assert_eq!( "f5a1".parse::<GameRecord>(), Err( RecordError::IllegalMove( 1, OthelloMove::from_str( "a1" ).unwrap() ) ) );
+        assert_eq!( "f5z9".parse::<GameRecord>(), Err( RecordError::Syntax( 2 ) ) );
+        assert_eq!( "f5d".parse::<GameRecord>(), Err( RecordError::Syntax( 2 ) ) );
+    }
+
+    #[test]
+    fn finished_transcripts_get_a_result()
+    {
+        let record = played_record();
+        let parsed = record.transcript().parse::<GameRecord>().unwrap();
+        assert_eq!( parsed.result(), record.result() );
+        assert_eq!( "F5 d6".parse::<GameRecord>().unwrap().result(), None );
+    }
+}
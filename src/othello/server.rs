@@ -0,0 +1,610 @@
+//Games over TCP. A GameServer hosts an OthelloGame whose network seats are
+//NetworkPlayers: their moves come from the clients sitting at them. The line
+//protocol is, from the client:
+//  join black|white [token]   take a seat; the token takes it back after a
+//                             disconnect
+//  join watch                 follow the game as a spectator
+//  play square                the move, when asked with go; PASS to pass
+//and from the server:
+//  ok role [token]            answer to join
+//  error message
+//  position board             the position in board notation, on joining
+//                             and before every move
+//  go                         asks the seated client for its move
+//  result score               at the end, like B+12, 0 or W+forfeit
+//A client that drops out can reconnect and continue as long as its player's
+//time for the move has not run out. Games played with GameServer::play show
+//every position; games played otherwise only those a network player is to
+//move in. Lines to a client are queued and written by a thread of its own,
+//so a client that does not read cannot hold up the game.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::{OthelloSituation, OthelloMove, Player};
+use super::record::GameResult;
+use super::super::OthelloGame;
+use super::super::ai::Random;
+use super::super::game::{self, GameSituation, MoveNotation};
+
+//A client that takes longer than this to accept a line is disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_secs( 10 );
+
+//What a seat's connection tells its player
+enum Event
+{
+    Joined,
+    Line( String ),
+    Left
+}
+
+//The sending side of a client
+struct Connection
+{
+    lines: Sender<String>,
+    //For disconnecting the client
+    stream: TcpStream
+}
+
+impl Connection
+{
+    //Starts the thread writing the queued lines to stream. If a write fails
+    //the stream is shut down, which also ends the reading side
+    fn new( stream: &TcpStream ) -> io::Result<Connection>
+    {
+        stream.set_write_timeout( Some( WRITE_TIMEOUT ) )?;
+        let mut writer = stream.try_clone()?;
+        let ( lines, queue ) = mpsc::channel::<String>();
+        thread::spawn( move ||
+        {
+            for line in queue
+            {
+                if writeln!( writer, "{}", line ).is_err()
+                {
+                    let _ = writer.shutdown( Shutdown::Both );
+                    break;
+                }
+            }
+        });
+        Ok( Connection{ lines, stream: stream.try_clone()? } )
+    }
+
+    //Queues the line, returns false once the connection has failed
+    fn send( &self, line: &str ) -> bool
+    {
+        self.lines.send( line.to_string() ).is_ok()
+    }
+}
+
+struct Seat
+{
+    //The player of the seat, None until the server creates it
+    events: Option<Sender<Event>>,
+    token: Option<String>,
+    connection: Option<Connection>
+}
+
+struct Hub
+{
+    seats: [Seat; 2],
+    spectators: Vec<Connection>,
+    //The latest position and result lines, for those who join later
+    position: Option<String>,
+    result: Option<String>
+}
+
+fn seat_index( role: &Player ) -> usize
+{
+    if *role == Player::Black { 0 } else { 1 }
+}
+
+fn role_name( role: &Player ) -> &'static str
+{
+    if *role == Player::Black { "black" } else { "white" }
+}
+
+impl Hub
+{
+    //Sends the line to the client at the seat, if any, forgetting it if it
+    //has gone
+    fn send_to_seat( &mut self, index: usize, line: &str )
+    {
+        let seat = &mut self.seats[index];
+        if seat.connection.as_ref().is_some_and( |connection| !connection.send( line ) )
+        {
+            seat.connection = None;
+        }
+    }
+
+    //Sends the line to the seated clients and the spectators, forgetting
+    //those that have gone
+    fn broadcast( &mut self, line: &str )
+    {
+        for index in 0..self.seats.len()
+        {
+            self.send_to_seat( index, line );
+        }
+        self.spectators.retain( |connection| connection.send( line ) );
+    }
+
+    //Sends the position unless it was the last one sent
+    fn show_position( &mut self, situation: &OthelloSituation )
+    {
+        let line = format!( "position {}", situation.notation() );
+        if self.position.as_ref() != Some( &line )
+        {
+            self.broadcast( &line );
+            self.position = Some( line );
+        }
+    }
+
+    //The position and result so far, for a client that has just joined
+    fn catch_up( &self, connection: &Connection )
+    {
+        for line in self.position.iter().chain( self.result.iter() )
+        {
+            connection.send( line );
+        }
+    }
+}
+
+pub struct GameServer
+{
+    hub: Arc<Mutex<Hub>>,
+    address: SocketAddr,
+    stopped: Arc<AtomicBool>
+}
+
+impl GameServer
+{
+    //Starts accepting clients at the address, port 0 picks a free port
+    pub fn bind<A: ToSocketAddrs>( address: A ) -> io::Result<GameServer>
+    {
+        let listener = TcpListener::bind( address )?;
+        let address = listener.local_addr()?;
+        let no_seat = || Seat{ events: None, token: None, connection: None };
+        let hub = Arc::new( Mutex::new( Hub{ seats: [ no_seat(), no_seat() ], spectators: Vec::new(), position: None, result: None } ) );
+        let stopped = Arc::new( AtomicBool::new( false ) );
+        let ( accept_hub, accept_stopped ) = ( hub.clone(), stopped.clone() );
+        thread::spawn( move ||
+        {
+            for stream in listener.incoming()
+            {
+                if accept_stopped.load( Ordering::SeqCst )
+                {
+                    break;
+                }
+                if let Ok( stream ) = stream
+                {
+                    let hub = accept_hub.clone();
+                    thread::spawn( move || serve( hub, stream ) );
+                }
+            }
+        });
+        Ok( GameServer{ hub, address, stopped } )
+    }
+
+    pub fn local_addr( &self ) -> SocketAddr
+    {
+        self.address
+    }
+
+    //The player for the seat of role, moving for whoever sits there. A
+    //client that does not move within time_per_move forfeits
+    pub fn player( &self, role: Player, time_per_move: Duration ) -> NetworkPlayer
+    {
+        let ( sender, events ) = mpsc::channel();
+        self.hub.lock().unwrap().seats[seat_index( &role )].events = Some( sender );
+        NetworkPlayer{ hub: self.hub.clone(), role, events, time_per_move }
+    }
+
+    //Plays the game, showing everyone each position and the result
+    pub fn play( &self, game: &mut OthelloGame ) -> Option<Player>
+    {
+        let winner = game.play_observed( |situation| self.hub.lock().unwrap().show_position( situation ) );
+        let mut hub = self.hub.lock().unwrap();
+        let final_situation = game.record().final_situation().ok();
+        if let Some( ref situation ) = final_situation
+        {
+            hub.show_position( situation );
+        }
+        let result = match ( game.record().result(), final_situation )
+        {
+            ( Some( &GameResult::Forfeit( Player::Black ) ), _ ) => "B+forfeit".to_string(),
+            ( Some( &GameResult::Forfeit( Player::White ) ), _ ) => "W+forfeit".to_string(),
+            ( _, Some( situation ) ) => situation.format_result(),
+            _ => "0".to_string()
+        };
+        let line = format!( "result {}", result );
+        hub.broadcast( &line );
+        hub.result = Some( line );
+        winner
+    }
+}
+
+//Disconnects everyone
+impl Drop for GameServer
+{
+    fn drop( &mut self )
+    {
+        self.stopped.store( true, Ordering::SeqCst );
+        //Wakes up the accepting thread
+        let _ = TcpStream::connect( self.address );
+        let mut hub = self.hub.lock().unwrap();
+        for connection in hub.seats.iter().filter_map( |seat| seat.connection.as_ref() ).chain( hub.spectators.iter() )
+        {
+            let _ = connection.stream.shutdown( Shutdown::Both );
+        }
+        hub.spectators.clear();
+    }
+}
+
+fn serve( hub: Arc<Mutex<Hub>>, stream: TcpStream )
+{
+    let _ = try_serve( hub, stream );
+}
+
+fn try_serve( hub: Arc<Mutex<Hub>>, stream: TcpStream ) -> io::Result<()>
+{
+    let connection = Connection::new( &stream )?;
+    let mut lines = BufReader::new( stream ).lines();
+    let join = match lines.next()
+    {
+        Some( line ) => line?,
+        None => return Ok( () )
+    };
+    let words = join.split_whitespace().collect::<Vec<_>>();
+    let role = match words.get( 1 ).cloned()
+    {
+        Some( "watch" ) if words[0] == "join" =>
+        {
+            let mut hub = hub.lock().unwrap();
+            connection.send( "ok watch" );
+            hub.catch_up( &connection );
+            //Spectators that leave are forgotten on the next broadcast
+            hub.spectators.push( connection );
+            return Ok( () );
+        },
+        Some( role ) if words[0] == "join" => OthelloSituation::parse_role( role ),
+        _ => None
+    };
+    let role = match role
+    {
+        Some( role ) => role,
+        None =>
+        {
+            connection.send( "error expected join black, white or watch" );
+            return Ok( () );
+        }
+    };
+    let events = {
+        let mut hub = hub.lock().unwrap();
+        let index = seat_index( &role );
+        let refusal = match hub.seats[index]
+        {
+            Seat{ events: None, .. } => Some( "the seat is not open" ),
+            Seat{ connection: Some( _ ), .. } => Some( "the seat is taken" ),
+            Seat{ token: Some( ref token ), .. } if words.get( 2 ) != Some( &token.as_str() ) => Some( "the seat is taken" ),
+            _ => None
+        };
+        if let Some( refusal ) = refusal
+        {
+            connection.send( &format!( "error {}", refusal ) );
+            return Ok( () );
+        }
+        let token = hub.seats[index].token.clone().unwrap_or_else( || format!( "{:016x}", Random::from_clock().next_u64() ) );
+        //Kept even if the client is gone before the answer reaches it
+        hub.seats[index].token = Some( token.clone() );
+        connection.send( &format!( "ok {} {}", role_name( &role ), token ) );
+        hub.catch_up( &connection );
+        let seat = &mut hub.seats[index];
+        seat.connection = Some( connection );
+        let events = seat.events.clone().unwrap();
+        let _ = events.send( Event::Joined );
+        events
+    };
+    for line in lines
+    {
+        match line
+        {
+            Ok( line ) => if events.send( Event::Line( line ) ).is_err() { break },
+            Err( _ ) => break
+        }
+    }
+    hub.lock().unwrap().seats[seat_index( &role )].connection = None;
+    let _ = events.send( Event::Left );
+    Ok( () )
+}
+
+pub struct NetworkPlayer
+{
+    hub: Arc<Mutex<Hub>>,
+    role: Player,
+    events: Receiver<Event>,
+    time_per_move: Duration
+}
+
+impl NetworkPlayer
+{
+    fn send( &self, line: &str )
+    {
+        self.hub.lock().unwrap().send_to_seat( seat_index( &self.role ), line );
+    }
+}
+
+impl game::Player for NetworkPlayer
+{
+    type Move = OthelloMove;
+    type Situation = OthelloSituation;
+    //Illegal or unreadable moves are refused and the client may try again
+    //until the time runs out
+    fn make_move( &mut self, situation: &OthelloSituation, _previous_move: Option<OthelloMove> ) -> Option<OthelloMove>
+    {
+        let deadline = Instant::now() + self.time_per_move;
+        self.hub.lock().unwrap().show_position( situation );
+        //Anything sent while it was not this player's turn
+        while self.events.try_recv().is_ok() {}
+        self.send( "go" );
+        loop
+        {
+            match self.events.recv_timeout( deadline.saturating_duration_since( Instant::now() ) )
+            {
+                Ok( Event::Joined ) => self.send( "go" ),
+                Ok( Event::Left ) => (),
+                Ok( Event::Line( line ) ) =>
+                {
+                    let mut words = line.split_whitespace();
+                    if words.next() != Some( "play" )
+                    {
+                        self.send( "error expected play" );
+                        continue;
+                    }
+                    match words.next().and_then( |text| situation.parse_move( text ) )
+                    {
+                        Some( the_move ) if situation.copy_apply( the_move.clone() ).is_some() => return Some( the_move ),
+                        _ => self.send( "error illegal move" )
+                    }
+                },
+                Err( _ ) => return None
+            }
+        }
+    }
+}
+
+//Takes the seat of role at the server and plays the game with player.
+//Returns the result line's score. token is set to the seat's token once
+//joined; after a lost connection, calling again with it takes the seat back
+pub fn run_client<A, P>( address: A, role: Player, token: &mut Option<String>, player: &mut P ) -> io::Result<String>
+    where A: ToSocketAddrs, P: game::Player<Situation = OthelloSituation, Move = OthelloMove>
+{
+    let mut stream = TcpStream::connect( address )?;
+    match *token
+    {
+        Some( ref token ) => writeln!( stream, "join {} {}", role_name( &role ), token )?,
+        None => writeln!( stream, "join {}", role_name( &role ) )?
+    }
+    let mut situation = OthelloSituation::new();
+    for line in BufReader::new( stream.try_clone()? ).lines()
+    {
+        let line = line?;
+        let mut words = line.splitn( 2, ' ' );
+        let invalid = |message: String| io::Error::new( io::ErrorKind::InvalidData, message );
+        match ( words.next().unwrap_or( "" ), words.next().unwrap_or( "" ) )
+        {
+            ( "ok", seat ) => *token = seat.split_whitespace().nth( 1 ).map( str::to_string ),
+            ( "position", board ) => situation = board.parse().map_err( |error| invalid( format!( "{}", error ) ) )?,
+            ( "go", _ ) =>
+            {
+                let the_move = player.make_move( &situation, None ).ok_or_else( || invalid( "the player gave up".to_string() ) )?;
+                writeln!( stream, "play {}", situation.format_move( &the_move ) )?;
+            },
+            ( "result", score ) => return Ok( score.to_string() ),
+            ( "error", message ) => return Err( invalid( message.to_string() ) ),
+            _ => ()
+        }
+    }
+    Err( io::Error::new( io::ErrorKind::UnexpectedEof, "the server closed the connection" ) )
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use othello::players::{DummyOthelloPlayer, OthelloAlphaBetaPlayer};
+
+    struct Client
+    {
+        stream: TcpStream,
+        lines: io::Lines<BufReader<TcpStream>>
+    }
+
+    impl Client
+    {
+        fn connect( address: SocketAddr, join: &str ) -> Client
+        {
+            let mut stream = TcpStream::connect( address ).unwrap();
+            stream.set_read_timeout( Some( Duration::from_secs( 10 ) ) ).unwrap();
+            writeln!( stream, "{}", join ).unwrap();
+            let lines = BufReader::new( stream.try_clone().unwrap() ).lines();
+            Client{ stream, lines }
+        }
+
+        fn read( &mut self ) -> String
+        {
+            self.lines.next().unwrap().unwrap()
+        }
+
+        //Reads up to and including the first line starting with prefix
+        fn read_until( &mut self, prefix: &str ) -> String
+        {
+            loop
+            {
+                let line = self.read();
+                if line.starts_with( prefix )
+                {
+                    return line;
+                }
+            }
+        }
+
+        fn send( &mut self, line: &str )
+        {
+            writeln!( self.stream, "{}", line ).unwrap();
+        }
+
+        //Plays the first legal move whenever asked, returns the result
+        fn play_out( &mut self ) -> String
+        {
+            let mut situation = OthelloSituation::new();
+            loop
+            {
+                let line = self.read();
+                if let Some( board ) = line.strip_prefix( "position " )
+                {
+                    situation = board.parse().unwrap();
+                }
+                else if line == "go"
+                {
+                    let the_move = situation.get_moves().next().unwrap();
+                    let text = situation.format_move( &the_move );
+                    self.send( &format!( "play {}", text ) );
+                }
+                else if let Some( score ) = line.strip_prefix( "result " )
+                {
+                    return score.to_string();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clients_play_a_game_with_a_spectator()
+    {
+        let server = GameServer::bind( "127.0.0.1:0" ).unwrap();
+        let black = server.player( Player::Black, Duration::from_secs( 10 ) );
+        let white = server.player( Player::White, Duration::from_secs( 10 ) );
+        let address = server.local_addr();
+        let mut spectator = Client::connect( address, "join watch" );
+        assert_eq!( spectator.read(), "ok watch" );
+        let clients = [ ( Player::Black, 2 ), ( Player::White, 3 ) ].iter().cloned().map( |( role, depth )| thread::spawn( move ||
+        {
+            run_client( address, role, &mut None, &mut OthelloAlphaBetaPlayer::new( depth ) ).unwrap()
+        })).collect::<Vec<_>>();
+
+        let mut game = OthelloGame::new( Box::new( black ), Box::new( white ) );
+        let winner = server.play( &mut game );
+        let results = clients.into_iter().map( |client| client.join().unwrap() ).collect::<Vec<_>>();
+        let final_situation = game.record().final_situation().unwrap();
+        assert!( final_situation.is_finished() );
+        assert_eq!( winner, final_situation.get_winner() );
+        assert_eq!( results[0], final_situation.format_result() );
+        assert_eq!( results[1], results[0] );
+
+        //The spectator saw every position
+        let mut positions = Vec::new();
+        loop
+        {
+            let line = spectator.read();
+            if line.starts_with( "result" )
+            {
+                break;
+            }
+            positions.push( line );
+        }
+        assert_eq!( positions.first().unwrap(), &format!( "position {}", OthelloSituation::new().notation() ) );
+        assert_eq!( positions.last().unwrap(), &format!( "position {}", final_situation.notation() ) );
+        assert_eq!( positions.len(), game.record().moves().len() + 1 );
+    }
+
+    #[test]
+    fn seats_are_guarded_and_can_be_taken_back()
+    {
+        let server = GameServer::bind( "127.0.0.1:0" ).unwrap();
+        let address = server.local_addr();
+        let black = server.player( Player::Black, Duration::from_secs( 10 ) );
+        assert_eq!( Client::connect( address, "join white" ).read(), "error the seat is not open" );
+        assert_eq!( Client::connect( address, "hello" ).read(), "error expected join black, white or watch" );
+        let mut first = Client::connect( address, "join black" );
+        let ok = first.read();
+        assert!( ok.starts_with( "ok black " ) );
+        let token = ok.split_whitespace().nth( 2 ).unwrap().to_string();
+        assert_eq!( Client::connect( address, "join black" ).read(), "error the seat is taken" );
+
+        let client = thread::spawn( move ||
+        {
+            first.read_until( "go" );
+            first.send( "play A1" );
+            assert_eq!( first.read(), "error illegal move" );
+            first.send( "resign" );
+            assert_eq!( first.read(), "error expected play" );
+            drop( first );
+            thread::sleep( Duration::from_millis( 100 ) );
+            assert_eq!( Client::connect( address, "join black wrong" ).read(), "error the seat is taken" );
+            let mut again = Client::connect( address, &format!( "join black {}", token ) );
+            assert_eq!( again.read(), format!( "ok black {}", token ) );
+            assert_eq!( again.read(), format!( "position {}", OthelloSituation::new().notation() ) );
+            again.play_out()
+        });
+        let mut game = OthelloGame::new( Box::new( black ), Box::new( DummyOthelloPlayer::new() ) );
+        server.play( &mut game );
+        let final_situation = game.record().final_situation().unwrap();
+        assert!( final_situation.is_finished() );
+        assert_eq!( client.join().unwrap(), final_situation.format_result() );
+    }
+
+    #[test]
+    fn spectators_see_local_moves_and_clients_reconnect()
+    {
+        let server = GameServer::bind( "127.0.0.1:0" ).unwrap();
+        let address = server.local_addr();
+        let black = server.player( Player::Black, Duration::from_secs( 10 ) );
+        let mut spectator = Client::connect( address, "join watch" );
+        assert_eq!( spectator.read(), "ok watch" );
+        let client = thread::spawn( move ||
+        {
+            let mut first = Client::connect( address, "join black" );
+            let token = first.read().split_whitespace().nth( 2 ).map( str::to_string );
+            drop( first );
+            thread::sleep( Duration::from_millis( 100 ) );
+            let mut token = token;
+            let expected = token.clone();
+            let result = run_client( address, Player::Black, &mut token, &mut OthelloAlphaBetaPlayer::new( 1 ) ).unwrap();
+            assert_eq!( token, expected );
+            result
+        });
+        let mut game = OthelloGame::new( Box::new( black ), Box::new( DummyOthelloPlayer::new() ) );
+        server.play( &mut game );
+        let final_situation = game.record().final_situation().unwrap();
+        assert_eq!( client.join().unwrap(), final_situation.format_result() );
+
+        //Positions before the dummy's moves too
+        let mut positions = 0;
+        while !spectator.read().starts_with( "result" )
+        {
+            positions += 1;
+        }
+        assert_eq!( positions, game.record().moves().len() + 1 );
+    }
+
+    #[test]
+    fn silent_clients_forfeit()
+    {
+        let server = GameServer::bind( "127.0.0.1:0" ).unwrap();
+        let white = server.player( Player::White, Duration::from_millis( 200 ) );
+        let mut spectator = Client::connect( server.local_addr(), "join watch" );
+        let mut game = OthelloGame::new( Box::new( DummyOthelloPlayer::new() ), Box::new( white ) );
+        assert_eq!( server.play( &mut game ), Some( Player::Black ) );
+        assert_eq!( spectator.read_until( "result" ), "result B+forfeit" );
+        //Late comers get the final position and the result
+        let mut late = Client::connect( server.local_addr(), "join watch" );
+        assert_eq!( late.read(), "ok watch" );
+        assert!( late.read().starts_with( "position " ) );
+        assert_eq!( late.read(), "result B+forfeit" );
+    }
+}
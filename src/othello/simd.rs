@@ -0,0 +1,98 @@
+//AVX2 version of the Kogge-Stone flip computation: the eight directions are
+//filled at the same time, four left shifting ones in one vector and four
+//right shifting ones in another. Used if the CPU supports AVX2, unless the
+//kogge_stone feature asks for the portable version.
+
+use std::arch::x86_64::*;
+use super::kogge_stone::{NOT_A_FILE, NOT_H_FILE};
+
+pub fn has_avx2() -> bool
+{
+    is_x86_feature_detected!( "avx2" )
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn shift( bits: __m256i, amounts: __m256i, left: bool ) -> __m256i
+{
+    if left { _mm256_sllv_epi64( bits, amounts ) } else { _mm256_srlv_epi64( bits, amounts ) }
+}
+
+//Flips of the lines from placed through other ending in own, in the four
+//directions given by the shift amounts ( left shifts if left is true ) and
+//the masks clearing the squares the shifts wrap onto
+#[target_feature(enable = "avx2")]
+unsafe fn flips( own: __m256i, other: __m256i, placed: __m256i, amounts: __m256i, masks: __m256i, left: bool ) -> __m256i
+{
+    let amounts_2 = _mm256_add_epi64( amounts, amounts );
+    let amounts_4 = _mm256_add_epi64( amounts_2, amounts_2 );
+
+    let mut propagator = _mm256_and_si256( other, masks );
+    let mut generator = _mm256_or_si256( placed, _mm256_and_si256( propagator, shift( placed, amounts, left ) ) );
+    propagator = _mm256_and_si256( propagator, shift( propagator, amounts, left ) );
+    generator = _mm256_or_si256( generator, _mm256_and_si256( propagator, shift( generator, amounts_2, left ) ) );
+    propagator = _mm256_and_si256( propagator, shift( propagator, amounts_2, left ) );
+    generator = _mm256_or_si256( generator, _mm256_and_si256( propagator, shift( generator, amounts_4, left ) ) );
+
+    let line = _mm256_and_si256( generator, other );
+    let closed = _mm256_and_si256( _mm256_and_si256( shift( _mm256_or_si256( line, placed ), amounts, left ), masks ), own );
+    //All ones in the lanes where nothing closes the line
+    let open = _mm256_cmpeq_epi64( closed, _mm256_setzero_si256() );
+    _mm256_andnot_si256( open, line )
+}
+
+//Same result as the portable delta_for_move
+#[target_feature(enable = "avx2")]
+pub unsafe fn delta_for_move( own: u64, other: u64, placed: u64 ) -> u64
+{
+    if placed & ( own | other ) != 0
+    {
+        return 0;
+    }
+    let own_lanes = _mm256_set1_epi64x( own as i64 );
+    let other_lanes = _mm256_set1_epi64x( other as i64 );
+    let placed_lanes = _mm256_set1_epi64x( placed as i64 );
+    //Lanes from last to first: right, down, down left, down right and left,
+    //up, up right, up left
+    let amounts = _mm256_set_epi64x( 9, 7, 8, 1 );
+    let left_masks = _mm256_set_epi64x( NOT_A_FILE as i64, NOT_H_FILE as i64, -1, NOT_A_FILE as i64 );
+    let right_masks = _mm256_set_epi64x( NOT_H_FILE as i64, NOT_A_FILE as i64, -1, NOT_H_FILE as i64 );
+    let all = _mm256_or_si256( flips( own_lanes, other_lanes, placed_lanes, amounts, left_masks, true ),
+                               flips( own_lanes, other_lanes, placed_lanes, amounts, right_masks, false ) );
+    let halves = _mm_or_si128( _mm256_castsi256_si128( all ), _mm256_extracti128_si256( all, 1 ) );
+    ( _mm_cvtsi128_si64( halves ) | _mm_extract_epi64( halves, 1 ) ) as u64
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use super::super::{delta_for_move_stepwise, kogge_stone};
+    use bitboard::BitBoard;
+    use ai::Random;
+
+    #[test]
+    fn matches_portable_flips()
+    {
+        if !has_avx2()
+        {
+            return;
+        }
+        let mut random = Random::new( 13 );
+        for _ in 0..500
+        {
+            let occupied = random.next_u64() | random.next_u64();
+            let colors = random.next_u64();
+            let ( own, other ) = ( BitBoard::from_bits( occupied & colors ), BitBoard::from_bits( occupied & !colors ) );
+            for idx in 0..64
+            {
+                let placed = BitBoard::from_bits( 1 << idx );
+                let expected = delta_for_move_stepwise( own, other, placed );
+                assert_eq!( kogge_stone::delta_for_move( own, other, placed ).bits(), expected.bits() );
+                assert_eq!( unsafe { delta_for_move( own.bits(), other.bits(), placed.bits() ) }, expected.bits() );
+            }
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//Matches between player configurations. Every pairing plays rounds of two
+//games from the same opening with the colors swapped. The results are
+//reported as Elo differences with 95% error bars, and a sequential
+//probability ratio test can stop a pairing as soon as it is decided.
+
+use std::fmt;
+use super::{OthelloSituation, Player};
+use super::record::{GameRecord, GameResult, RecordError};
+use super::super::{OthelloGame, OthelloPlayer};
+
+//Normal quantile of the error bars
+const CONFIDENCE_Z: f64 = 1.96;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score
+{
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    //Own discs minus the opponent's, summed over the games
+    pub discs: i64
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate
+{
+    pub elo: f64,
+    //The 95% confidence interval
+    pub lower: f64,
+    pub upper: f64
+}
+
+//Expected score at an Elo difference, and back
+fn expected_score( elo: f64 ) -> f64
+{
+    1.0 / ( 1.0 + 10f64.powf( -elo / 400.0 ) )
+}
+
+fn elo_difference( score: f64 ) -> f64
+{
+    400.0 * ( score / ( 1.0 - score ) ).log10()
+}
+
+impl Score
+{
+    pub fn games( &self ) -> u32
+    {
+        self.wins + self.draws + self.losses
+    }
+
+    //Adds a game that ended with discs more or fewer for this side
+    pub fn add( &mut self, discs: i32 )
+    {
+        match discs
+        {
+            0 => self.draws += 1,
+            _ if discs > 0 => self.wins += 1,
+            _ => self.losses += 1
+        }
+        self.discs += discs as i64;
+    }
+
+    //The same games from the opponent's side
+    pub fn reversed( &self ) -> Score
+    {
+        Score{ wins: self.losses, draws: self.draws, losses: self.wins, discs: -self.discs }
+    }
+
+    //Wins and half the draws
+    pub fn points( &self ) -> f64
+    {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    //Mean and variance of the score of a game
+    fn mean_and_variance( &self ) -> ( f64, f64 )
+    {
+        let games = self.games() as f64;
+        let mean = self.points() / games;
+        let variance = ( self.wins as f64 * ( 1.0 - mean ).powi( 2 )
+                         + self.draws as f64 * ( 0.5 - mean ).powi( 2 )
+                         + self.losses as f64 * mean.powi( 2 ) ) / games;
+        ( mean, variance )
+    }
+
+    //None before the first game. Only wins or only losses give infinities
+    pub fn elo( &self ) -> Option<EloEstimate>
+    {
+        if self.games() == 0
+        {
+            return None;
+        }
+        let ( mean, variance ) = self.mean_and_variance();
+        let margin = CONFIDENCE_Z * ( variance / self.games() as f64 ).sqrt();
+        Some( EloEstimate{ elo: elo_difference( mean ),
+                           lower: elo_difference( ( mean - margin ).max( 0.0 ) ),
+                           upper: elo_difference( ( mean + margin ).min( 1.0 ) ) } )
+    }
+}
+
+impl fmt::Display for Score
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "+{} ={} -{} discs {:+}", self.wins, self.draws, self.losses, self.discs)?;
+        if let Some( estimate ) = self.elo()
+        {
+            write!(f, " elo {:+.0} [{:+.0}, {:+.0}]", estimate.elo, estimate.lower, estimate.upper)?;
+        }
+        Ok( () )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtResult
+{
+    Continue,
+    //The difference is at most elo0
+    AcceptH0,
+    //The difference is at least elo1
+    AcceptH1
+}
+
+//Tests elo0 against elo1 with error rates alpha and beta, using the normal
+//approximation of the log likelihood ratio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt
+{
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64
+}
+
+impl Sprt
+{
+    pub fn new( elo0: f64, elo1: f64 ) -> Sprt
+    {
+        Sprt{ elo0, elo1, alpha: 0.05, beta: 0.05 }
+    }
+
+    pub fn bounds( &self ) -> ( f64, f64 )
+    {
+        ( ( self.beta / ( 1.0 - self.alpha ) ).ln(), ( ( 1.0 - self.beta ) / self.alpha ).ln() )
+    }
+
+    //The variance is kept from zero so that one sided results get decided,
+    //though after a few games rather than at once
+    pub fn llr( &self, score: &Score ) -> f64
+    {
+        if score.games() == 0
+        {
+            return 0.0;
+        }
+        let ( mean, variance ) = score.mean_and_variance();
+        let ( score0, score1 ) = ( expected_score( self.elo0 ), expected_score( self.elo1 ) );
+        ( score1 - score0 ) * ( 2.0 * mean - score0 - score1 ) * score.games() as f64 / ( 2.0 * variance.max( 0.05 ) )
+    }
+
+    pub fn test( &self, score: &Score ) -> SprtResult
+    {
+        let llr = self.llr( score );
+        let ( lower, upper ) = self.bounds();
+        if llr <= lower { SprtResult::AcceptH0 } else if llr >= upper { SprtResult::AcceptH1 } else { SprtResult::Continue }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format
+{
+    //Everyone plays everyone
+    RoundRobin,
+    //The first player plays everyone else
+    Gauntlet
+}
+
+struct Entrant
+{
+    name: String,
+    //A fresh player for every game
+    make: Box<dyn Fn() -> Box<OthelloPlayer>>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match
+{
+    //Indices of the players, the score is the first one's
+    pub first: usize,
+    pub second: usize,
+    pub score: Score,
+    //None without an SPRT
+    pub sprt: Option<SprtResult>
+}
+
+pub struct Tournament
+{
+    entrants: Vec<Entrant>,
+    format: Format,
+    rounds: u32,
+    openings: Vec<OthelloSituation>,
+    sprt: Option<Sprt>
+}
+
+impl Tournament
+{
+    //Each pairing plays rounds times two games
+    pub fn new( format: Format, rounds: u32 ) -> Tournament
+    {
+        Tournament{ entrants: Vec::new(), format, rounds, openings: Vec::new(), sprt: None }
+    }
+
+    pub fn add_player<F>( &mut self, name: &str, make: F )
+        where F: Fn() -> Box<OthelloPlayer> + 'static
+    {
+        self.entrants.push( Entrant{ name: name.to_string(), make: Box::new( make ) } );
+    }
+
+    //Round i starts from opening i, going round the list. Without openings
+    //the games start from the start position
+    pub fn set_openings( &mut self, openings: Vec<OthelloSituation> )
+    {
+        self.openings = openings;
+    }
+
+    //Stops a pairing once the test decides
+    pub fn set_sprt( &mut self, sprt: Sprt )
+    {
+        self.sprt = Some( sprt );
+    }
+
+    fn pairings( &self ) -> Vec<( usize, usize )>
+    {
+        let count = self.entrants.len();
+        match self.format
+        {
+            Format::RoundRobin => ( 0..count ).flat_map( |first| ( first + 1..count ).map( move |second| ( first, second ) ) ).collect(),
+            Format::Gauntlet => ( 1..count ).map( |second| ( 0, second ) ).collect()
+        }
+    }
+
+    //Black's discs minus white's, a forfeit counting as 64
+    fn play_game( &self, black: usize, white: usize, start: &OthelloSituation ) -> i32
+    {
+        let mut game = OthelloGame::with_start( ( self.entrants[black].make )(), ( self.entrants[white].make )(), start.clone() );
+        game.play();
+        match game.record().result()
+        {
+            Some( &GameResult::Discs( discs ) ) => discs,
+            Some( &GameResult::Forfeit( Player::Black ) ) => 64,
+            _ => -64
+        }
+    }
+
+    pub fn run( &self ) -> TournamentResult
+    {
+        let start = OthelloSituation::new();
+        let mut matches = Vec::new();
+        for ( first, second ) in self.pairings()
+        {
+            let mut score = Score::default();
+            let mut sprt = self.sprt.map( |_| SprtResult::Continue );
+            for round in 0..self.rounds as usize
+            {
+                let opening = if self.openings.is_empty() { &start } else { &self.openings[round % self.openings.len()] };
+                score.add( self.play_game( first, second, opening ) );
+                score.add( -self.play_game( second, first, opening ) );
+                sprt = self.sprt.map( |test| test.test( &score ) );
+                if sprt.is_some_and( |result| result != SprtResult::Continue )
+                {
+                    break;
+                }
+            }
+            matches.push( Match{ first, second, score, sprt } );
+        }
+        TournamentResult{ names: self.entrants.iter().map( |entrant| entrant.name.clone() ).collect(), matches }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentResult
+{
+    pub names: Vec<String>,
+    pub matches: Vec<Match>
+}
+
+impl TournamentResult
+{
+    //Each player's score against the field, best first
+    pub fn standings( &self ) -> Vec<( String, Score )>
+    {
+        let mut totals = vec![ Score::default(); self.names.len() ];
+        for a_match in self.matches.iter()
+        {
+            for &( player, score ) in [ ( a_match.first, a_match.score ), ( a_match.second, a_match.score.reversed() ) ].iter()
+            {
+                let total = &mut totals[player];
+                total.wins += score.wins;
+                total.draws += score.draws;
+                total.losses += score.losses;
+                total.discs += score.discs;
+            }
+        }
+        let mut standings = self.names.iter().cloned().zip( totals ).collect::<Vec<_>>();
+        //Stable, so ties keep the order the players were added in
+        standings.sort_by( |a, b| b.1.points().partial_cmp( &a.1.points() ).unwrap().then( b.1.discs.cmp( &a.1.discs ) ) );
+        standings
+    }
+}
+
+impl fmt::Display for TournamentResult
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        for a_match in self.matches.iter()
+        {
+            write!(f, "{} - {}: {}", self.names[a_match.first], self.names[a_match.second], a_match.score)?;
+            match a_match.sprt
+            {
+                Some( SprtResult::AcceptH0 ) => writeln!(f, " SPRT H0")?,
+                Some( SprtResult::AcceptH1 ) => writeln!(f, " SPRT H1")?,
+                Some( SprtResult::Continue ) => writeln!(f, " SPRT undecided")?,
+                None => writeln!(f)?
+            }
+        }
+        for ( rank, ( name, score ) ) in self.standings().iter().enumerate()
+        {
+            writeln!(f, "{:2}. {:16} {:5.1}/{:<4} {}", rank + 1, name, score.points(), score.games(), score)?;
+        }
+        Ok( () )
+    }
+}
+
+//Positions to start games from, one transcript per line like the XOT lists.
+//Empty lines and lines starting with # are skipped
+pub fn openings_from_transcripts( text: &str ) -> Result<Vec<OthelloSituation>, RecordError>
+{
+    text.lines().map( str::trim ).filter( |line| !line.is_empty() && !line.starts_with( '#' ) )
+        .map( |line| GameRecord::from_transcript( OthelloSituation::new(), line )?.final_situation() )
+        .collect()
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use othello::players::{DummyOthelloPlayer, OthelloMinMaxPlayer};
+
+    fn score( wins: u32, draws: u32, losses: u32 ) -> Score
+    {
+        Score{ wins, draws, losses, discs: 0 }
+    }
+
+    #[test]
+    fn elo_from_scores()
+    {
+        assert_eq!( Score::default().elo(), None );
+        let even = score( 10, 10, 10 ).elo().unwrap();
+        assert_eq!( even.elo, 0.0 );
+        assert!( ( even.upper + even.lower ).abs() < 1e-9 );
+        let ahead = score( 60, 30, 10 ).elo().unwrap();
+        assert!( ( ahead.elo - 190.85 ).abs() < 0.01, "{:?}", ahead );
+        assert!( ahead.lower < ahead.elo && ahead.elo < ahead.upper );
+        //More games, narrower bars
+        let more = score( 600, 300, 100 ).elo().unwrap();
+        assert!( more.upper - more.lower < ( ahead.upper - ahead.lower ) / 2.0 );
+        assert_eq!( score( 3, 0, 0 ).elo().unwrap().elo, f64::INFINITY );
+        assert_eq!( score( 2, 1, 3 ).reversed(), score( 3, 1, 2 ) );
+    }
+
+    #[test]
+    fn sprt_decides()
+    {
+        let sprt = Sprt::new( 0.0, 50.0 );
+        assert_eq!( sprt.test( &Score::default() ), SprtResult::Continue );
+        assert_eq!( sprt.test( &score( 6, 2, 4 ) ), SprtResult::Continue );
+        assert_eq!( sprt.test( &score( 300, 100, 200 ) ), SprtResult::AcceptH1 );
+        assert_eq!( sprt.test( &score( 200, 100, 300 ) ), SprtResult::AcceptH0 );
+        assert_eq!( sprt.test( &score( 20, 0, 0 ) ), SprtResult::AcceptH1 );
+        assert_eq!( sprt.test( &score( 0, 0, 2 ) ), SprtResult::Continue );
+        assert!( sprt.llr( &score( 50, 0, 50 ) ) < 0.0 );
+    }
+
+    fn tournament( format: Format, rounds: u32 ) -> Tournament
+    {
+        let mut tournament = Tournament::new( format, rounds );
+        tournament.add_player( "dummy", || Box::new( DummyOthelloPlayer::new() ) );
+        tournament.add_player( "minmax2", || Box::new( OthelloMinMaxPlayer::new( 2 ) ) );
+        tournament.add_player( "minmax3", || Box::new( OthelloMinMaxPlayer::new( 3 ) ) );
+        tournament
+    }
+
+    #[test]
+    fn round_robin_with_openings()
+    {
+        let mut tournament = tournament( Format::RoundRobin, 2 );
+        let openings = openings_from_transcripts( "# two openings\nf5d6c3d3c4f4\n\nf5f6e6f4e3c5\n" ).unwrap();
+        assert_eq!( openings.len(), 2 );
+        tournament.set_openings( openings );
+        let result = tournament.run();
+        let pairs = result.matches.iter().map( |a_match| ( a_match.first, a_match.second ) ).collect::<Vec<_>>();
+        assert_eq!( pairs, vec![ ( 0, 1 ), ( 0, 2 ), ( 1, 2 ) ] );
+        assert!( result.matches.iter().all( |a_match| a_match.score.games() == 4 && a_match.sprt.is_none() ) );
+
+        let standings = result.standings();
+        assert_eq!( standings.iter().map( |( _, score )| score.games() ).sum::<u32>(), 24 );
+        assert_eq!( standings.iter().map( |( _, score )| score.discs ).sum::<i64>(), 0 );
+        assert!( standings.windows( 2 ).all( |pair| pair[0].1.points() >= pair[1].1.points() ) );
+        let report = result.to_string();
+        assert!( report.contains( "dummy - minmax2: +" ) );
+        assert!( report.contains( &format!( " 1. {}", standings[0].0 ) ), "{}", report );
+    }
+
+    #[test]
+    fn gauntlet_stops_on_sprt()
+    {
+        let mut tournament = tournament( Format::Gauntlet, 50 );
+        tournament.set_sprt( Sprt::new( 0.0, 100.0 ) );
+        let result = tournament.run();
+        assert_eq!( result.matches.len(), 2 );
+        for a_match in result.matches.iter()
+        {
+            assert_eq!( a_match.first, 0 );
+            //Deterministic players repeat the same two games, so the
+            //dummy loses every one and the test stops early
+            assert_eq!( a_match.sprt, Some( SprtResult::AcceptH0 ) );
+            assert!( a_match.score.games() < 100 );
+        }
+    }
+
+    #[test]
+    fn bad_openings_are_reported()
+    {
+        assert_eq!( openings_from_transcripts( "f5d6\nf5f5\n" ), Err( RecordError::IllegalMove( 1, "f5".parse().unwrap() ) ) );
+    }
+}
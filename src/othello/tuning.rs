@@ -0,0 +1,311 @@
+//Tuning of the PatternOthelloEvaluator weights. Positions are labeled with
+//the final result of the game they come from, either from self-play or from
+//recorded games, and the weights are fitted to predict the labels by
+//gradient descent.
+
+use super::{OthelloSituation, OthelloMove};
+use super::evaluation::{self, PatternOthelloEvaluator, Weights, PHASES, FEATURES};
+use super::players::OthelloAlphaBetaPlayer;
+use super::super::ai::{Random, SearchLimits};
+use super::super::game::{GameSituation, Player};
+
+//Evaluator units per disc
+const DISC: f64 = 100.0;
+
+//A position labeled with the final disc differential of its game, from the
+//point of view of the side to move
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample
+{
+    pub phase: usize,
+    pub features: Vec<( usize, f64 )>,
+    pub score: i32
+}
+
+impl Sample
+{
+    fn new( situation: &OthelloSituation, final_situation: &OthelloSituation ) -> Sample
+    {
+        Sample{ phase: evaluation::phase( situation ),
+                features: evaluation::features( situation ),
+                score: final_situation.get_score( situation.get_turn() ) }
+    }
+}
+
+//Labels the positions of a finished game given as its moves from the start
+//position. Fails on illegal moves and unfinished games
+pub fn samples_from_game( moves: &[OthelloMove] ) -> Result<Vec<Sample>, String>
+{
+    let mut situations = vec![ OthelloSituation::new() ];
+    for a_move in moves
+    {
+        let next = situations.last().unwrap().copy_apply( a_move.clone() )
+            .ok_or_else( || format!( "Illegal move {} after {} moves", a_move, situations.len() - 1 ) )?;
+        situations.push( next );
+    }
+    let final_situation = situations.pop().unwrap();
+    if !final_situation.is_finished()
+    {
+        return Err( "The game is not finished".to_string() );
+    }
+    Ok( situations.iter().map( |situation| Sample::new( situation, &final_situation ) ).collect() )
+}
+
+#[derive(Clone, Debug)]
+pub struct SelfPlayConfig
+{
+    pub games: usize,
+    pub depth: u32,
+    //Random moves at the start of each game, so that the games differ
+    pub random_plies: usize,
+    pub endgame_empties: u32,
+    pub seed: u64
+}
+
+impl Default for SelfPlayConfig
+{
+    fn default() -> Self
+    {
+        SelfPlayConfig{ games: 100, depth: 4, random_plies: 8, endgame_empties: 14, seed: 1 }
+    }
+}
+
+//Plays games between two alpha-beta players using the given weights and
+//labels all the positions
+pub fn self_play_samples( config: &SelfPlayConfig, weights: &Weights ) -> Vec<Sample>
+{
+    let mut random = Random::new( config.seed );
+    let new_player = ||
+    {
+        let evaluator = PatternOthelloEvaluator::with_weights( weights.clone() );
+        let mut player = OthelloAlphaBetaPlayer::with_evaluator( SearchLimits::depth( config.depth ), evaluator );
+        player.set_endgame_empties( config.endgame_empties );
+        player
+    };
+    let mut samples = Vec::new();
+    for _ in 0..config.games
+    {
+        //Black's and white's
+        let mut players = [ new_player(), new_player() ];
+        let mut situation = OthelloSituation::new();
+        let mut situations = Vec::new();
+        let mut previous_move = None;
+        while !situation.is_finished()
+        {
+            let next_move = if situations.len() < config.random_plies
+            {
+                let moves = situation.get_moves().collect::<Vec<_>>();
+                moves[ random.below( moves.len() ) ].clone()
+            }
+            else
+            {
+                let side = if situation.get_turn() == super::Player::Black { 0 } else { 1 };
+                players[ side ].make_move( &situation, previous_move ).unwrap()
+            };
+            let next = situation.copy_apply( next_move.clone() ).unwrap();
+            situations.push( situation );
+            situation = next;
+            previous_move = Some( next_move );
+        }
+        //The random opening positions say little about the result
+        samples.extend( situations.iter().skip( config.random_plies ).map( |position| Sample::new( position, &situation ) ) );
+    }
+    samples
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective
+{
+    //Fit the evaluation to the final disc differential
+    LeastSquares,
+    //Texel style: fit sigmoid( scale * evaluation ) to the result of the
+    //game, 1 for a win, 0.5 for a draw and 0 for a loss
+    Logistic{ scale: f64 }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TuningConfig
+{
+    pub objective: Objective,
+    pub iterations: usize,
+    //Initial step size, in evaluator units
+    pub learning_rate: f64
+}
+
+impl Default for TuningConfig
+{
+    fn default() -> Self
+    {
+        TuningConfig{ objective: Objective::LeastSquares, iterations: 500, learning_rate: 10.0 }
+    }
+}
+
+fn sigmoid( x: f64 ) -> f64
+{
+    1.0 / ( 1.0 + ( -x ).exp() )
+}
+
+fn result( score: i32 ) -> f64
+{
+    ( score.signum() as f64 + 1.0 ) / 2.0
+}
+
+//Returns ( error, derivative of the error with respect to the evaluation )
+fn sample_error( sample: &Sample, weights: &Weights, objective: Objective ) -> ( f64, f64 )
+{
+    let evaluation = weights.dot( sample.phase, &sample.features );
+    match objective
+    {
+        Objective::LeastSquares =>
+        {
+            //In discs, to keep the numbers readable
+            let difference = ( evaluation - sample.score as f64 * DISC ) / DISC;
+            ( difference * difference, 2.0 * difference / DISC )
+        },
+        Objective::Logistic{ scale } =>
+        {
+            let prediction = sigmoid( scale * evaluation );
+            let difference = prediction - result( sample.score );
+            ( difference * difference, 2.0 * difference * prediction * ( 1.0 - prediction ) * scale )
+        }
+    }
+}
+
+//Mean error of the weights over the samples
+pub fn training_error( samples: &[Sample], weights: &Weights, objective: Objective ) -> f64
+{
+    if samples.is_empty()
+    {
+        return 0.0;
+    }
+    samples.iter().map( |sample| sample_error( sample, weights, objective ).0 ).sum::<f64>() / samples.len() as f64
+}
+
+//Full batch gradient descent with AdaGrad step sizes, so that rarely seen
+//features like the edge patterns still get reasonable updates
+pub fn tune( samples: &[Sample], initial: &Weights, config: &TuningConfig ) -> Weights
+{
+    let mut weights = initial.clone();
+    if samples.is_empty()
+    {
+        return weights;
+    }
+    let mut squared_gradients = vec![ vec![ 0.0; FEATURES ]; PHASES ];
+    for _ in 0..config.iterations
+    {
+        let mut gradient = vec![ vec![ 0.0; FEATURES ]; PHASES ];
+        for sample in samples
+        {
+            let derivative = sample_error( sample, &weights, config.objective ).1;
+            for &( feature, value ) in sample.features.iter()
+            {
+                gradient[sample.phase][feature] += derivative * value;
+            }
+        }
+        for phase in 0..PHASES
+        {
+            for feature in 0..FEATURES
+            {
+                let component = gradient[phase][feature] / samples.len() as f64;
+                if component == 0.0
+                {
+                    continue;
+                }
+                squared_gradients[phase][feature] += component * component;
+                let step = config.learning_rate * component / squared_gradients[phase][feature].sqrt();
+                let weight = weights.get( phase, feature );
+                weights.set( phase, feature, weight - step );
+            }
+        }
+    }
+    weights
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use std::env;
+    use std::fs;
+    use super::*;
+    use othello::evaluation::{MOBILITY, CORNERS, DISCS, EDGE_PATTERNS};
+
+    //Samples whose labels are a known linear function of the features
+    fn synthetic_samples() -> Vec<Sample>
+    {
+        let mut random = Random::new( 3 );
+        let true_weights = [ ( MOBILITY, 30.0 ), ( CORNERS, 400.0 ), ( DISCS, 50.0 ), ( EDGE_PATTERNS + 5, -200.0 ) ];
+        ( 0..400 ).map( |_|
+        {
+            let phase = random.below( PHASES );
+            let features = true_weights.iter()
+                .map( |&( feature, _ )| ( feature, random.below( 9 ) as f64 - 4.0 ) )
+                .collect::<Vec<_>>();
+            let evaluation = features.iter().zip( true_weights.iter() ).map( |( &( _, value ), &( _, weight ) )| value * weight ).sum::<f64>();
+            Sample{ phase, features, score: ( evaluation / DISC ).round() as i32 }
+        }).collect()
+    }
+
+    #[test]
+    fn least_squares_reduces_error()
+    {
+        let samples = synthetic_samples();
+        let config = TuningConfig{ objective: Objective::LeastSquares, iterations: 300, learning_rate: 50.0 };
+        let before = training_error( &samples, &Weights::zero(), config.objective );
+        let weights = tune( &samples, &Weights::zero(), &config );
+        let after = training_error( &samples, &weights, config.objective );
+        assert!( after < before * 0.05, "error {} -> {}", before, after );
+        assert!( ( weights.get( 1, CORNERS ) - 400.0 ).abs() < 40.0 );
+    }
+
+    #[test]
+    fn logistic_reduces_error()
+    {
+        let samples = synthetic_samples();
+        let config = TuningConfig{ objective: Objective::Logistic{ scale: 1.0 / 200.0 }, iterations: 300, learning_rate: 50.0 };
+        let before = training_error( &samples, &Weights::zero(), config.objective );
+        let weights = tune( &samples, &Weights::zero(), &config );
+        let after = training_error( &samples, &weights, config.objective );
+        assert!( after < before * 0.5, "error {} -> {}", before, after );
+    }
+
+    #[test]
+    fn tuned_weights_can_be_loaded()
+    {
+        let config = TuningConfig{ iterations: 20, ..Default::default() };
+        let weights = tune( &synthetic_samples(), &Weights::default(), &config );
+        let path = env::temp_dir().join( format!( "bit_board_tuned_{}.txt", std::process::id() ) );
+        weights.save( &path ).unwrap();
+        let evaluator = PatternOthelloEvaluator::from_file( &path ).unwrap();
+        fs::remove_file( &path ).unwrap();
+        assert_eq!( evaluator.weights(), &weights );
+    }
+
+    #[test]
+    fn game_samples_are_labeled_for_side_to_move()
+    {
+        let mut situation = OthelloSituation::new();
+        let mut moves = Vec::new();
+        while !situation.is_finished()
+        {
+            let a_move = situation.get_moves().next().unwrap();
+            situation = situation.copy_apply( a_move.clone() ).unwrap();
+            moves.push( a_move );
+        }
+        let samples = samples_from_game( &moves ).unwrap();
+        assert_eq!( samples.len(), moves.len() );
+        assert_eq!( samples[0].score, situation.get_score( super::super::Player::Black ) );
+        assert!( samples_from_game( &moves[..10] ).is_err() );
+        assert!( samples_from_game( &[ OthelloMove::Pass ] ).is_err() );
+    }
+
+    #[test]
+    fn self_play_produces_samples()
+    {
+        let config = SelfPlayConfig{ games: 2, depth: 1, random_plies: 4, endgame_empties: 0, seed: 5 };
+        let samples = self_play_samples( &config, &Weights::default() );
+        assert!( samples.len() > 2 * 40 );
+        assert!( samples.iter().all( |sample| sample.phase < PHASES ) );
+    }
+}
@@ -0,0 +1,292 @@
+//Import of the WTHOR database of the French Othello Federation. A .wtb file
+//holds the games, .jou the player names and .trn the tournament names; the
+//games refer to the names by their index. Every file starts with a 16 byte
+//header, followed by fixed size records:
+//  .wtb  68 bytes: tournament, black and white player (u16 little endian),
+//        black discs at the end, black discs with perfect play from the
+//        solved depth, then 60 moves as 10 * row + column from 11 (a1) to
+//        88 (h8), 0 after the end. Passes are left out
+//  .jou  20 bytes: a player name, zero padded Latin-1
+//  .trn  26 bytes: a tournament name, zero padded Latin-1
+
+use std::io::{self, Read, ErrorKind};
+use super::OthelloSituation;
+use super::record::GameRecord;
+use super::super::bitboard::Coord;
+
+const HEADER_SIZE: usize = 16;
+const GAME_SIZE: usize = 68;
+const PLAYER_SIZE: usize = 20;
+const TOURNAMENT_SIZE: usize = 26;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WthorHeader
+{
+    //Date the file was written
+    pub created: ( u32, u8, u8 ),
+    //Number of games in a .wtb file
+    pub games: u32,
+    //Number of names in a .jou or .trn file
+    pub names: u16,
+    //Year the games were played
+    pub year: u16,
+    //Depth from which the theoretical scores are exact
+    pub depth: u8
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WthorGame
+{
+    pub tournament: u16,
+    pub black: u16,
+    pub white: u16,
+    pub black_discs: u8,
+    pub theoretical_black_discs: u8,
+    pub moves: Vec<Coord>
+}
+
+impl WthorGame
+{
+    //Replays the moves, adding the passes
+    pub fn to_record( &self ) -> io::Result<GameRecord>
+    {
+        let transcript = self.moves.iter().map( Coord::algebraic ).collect::<String>();
+        GameRecord::from_transcript( OthelloSituation::new(), &transcript )
+            .map_err( |error| io::Error::new( ErrorKind::InvalidData, error.to_string() ) )
+    }
+
+    pub fn black_name<'a>( &self, players: &'a [String] ) -> Option<&'a str>
+    {
+        players.get( self.black as usize ).map( String::as_str )
+    }
+
+    pub fn white_name<'a>( &self, players: &'a [String] ) -> Option<&'a str>
+    {
+        players.get( self.white as usize ).map( String::as_str )
+    }
+
+    pub fn tournament_name<'a>( &self, tournaments: &'a [String] ) -> Option<&'a str>
+    {
+        tournaments.get( self.tournament as usize ).map( String::as_str )
+    }
+}
+
+fn read_header<R: Read>( reader: &mut R ) -> io::Result<WthorHeader>
+{
+    let mut bytes = [0u8; HEADER_SIZE];
+    reader.read_exact( &mut bytes )?;
+    if bytes[12] != 0 && bytes[12] != 8
+    {
+        return Err( io::Error::new( ErrorKind::InvalidData, format!( "board size {} is not supported", bytes[12] ) ) );
+    }
+    Ok( WthorHeader{ created: ( bytes[0] as u32 * 100 + bytes[1] as u32, bytes[2], bytes[3] ),
+                     games: u32::from_le_bytes( [ bytes[4], bytes[5], bytes[6], bytes[7] ] ),
+                     names: u16::from_le_bytes( [ bytes[8], bytes[9] ] ),
+                     year: u16::from_le_bytes( [ bytes[10], bytes[11] ] ),
+                     depth: bytes[14] } )
+}
+
+fn parse_game( bytes: &[u8] ) -> io::Result<WthorGame>
+{
+    let invalid = |message: String| io::Error::new( ErrorKind::InvalidData, message );
+    let mut moves = Vec::new();
+    for &square in bytes[8..].iter().take_while( |&&square| square != 0 )
+    {
+        let ( row, col ) = ( ( square / 10 ) as u32, ( square % 10 ) as u32 );
+        let coord = row.checked_sub( 1 ).zip( col.checked_sub( 1 ) )
+            .and_then( |( row, col )| Coord::new( row, col ) )
+            .ok_or_else( || invalid( format!( "invalid move {}", square ) ) )?;
+        moves.push( coord );
+    }
+    let black_discs = bytes[6];
+    if black_discs > 64 || bytes[7] > 64
+    {
+        return Err( invalid( format!( "invalid score {}", black_discs ) ) );
+    }
+    Ok( WthorGame{ tournament: u16::from_le_bytes( [ bytes[0], bytes[1] ] ),
+                   black: u16::from_le_bytes( [ bytes[2], bytes[3] ] ),
+                   white: u16::from_le_bytes( [ bytes[4], bytes[5] ] ),
+                   black_discs, theoretical_black_discs: bytes[7], moves } )
+}
+
+//Streams the games of a .wtb file
+pub struct WthorGames<R: Read>
+{
+    reader: R,
+    header: WthorHeader,
+    remaining: u32
+}
+
+impl<R: Read> WthorGames<R>
+{
+    pub fn new( mut reader: R ) -> io::Result<WthorGames<R>>
+    {
+        let header = read_header( &mut reader )?;
+        Ok( WthorGames{ reader, remaining: header.games, header } )
+    }
+
+    pub fn header( &self ) -> &WthorHeader
+    {
+        &self.header
+    }
+}
+
+impl<R: Read> Iterator for WthorGames<R>
+{
+    type Item = io::Result<WthorGame>;
+    fn next( &mut self ) -> Option<io::Result<WthorGame>>
+    {
+        if self.remaining == 0
+        {
+            return None;
+        }
+        self.remaining -= 1;
+        let mut bytes = [0u8; GAME_SIZE];
+        if let Err( error ) = self.reader.read_exact( &mut bytes )
+        {
+            self.remaining = 0;
+            return Some( Err( error ) );
+        }
+        Some( parse_game( &bytes ) )
+    }
+}
+
+fn read_names<R: Read>( mut reader: R, size: usize ) -> io::Result<Vec<String>>
+{
+    let header = read_header( &mut reader )?;
+    let mut bytes = vec![ 0u8; size ];
+    let mut names = Vec::with_capacity( header.names as usize );
+    for _ in 0..header.names
+    {
+        reader.read_exact( &mut bytes )?;
+        //Latin-1 maps directly to the first 256 code points
+        names.push( bytes.iter().take_while( |&&byte| byte != 0 ).map( |&byte| byte as char ).collect::<String>().trim_end().to_string() );
+    }
+    Ok( names )
+}
+
+//The names of a .jou file, indexed by player number
+pub fn read_players<R: Read>( reader: R ) -> io::Result<Vec<String>>
+{
+    read_names( reader, PLAYER_SIZE )
+}
+
+//The names of a .trn file, indexed by tournament number
+pub fn read_tournaments<R: Read>( reader: R ) -> io::Result<Vec<String>>
+{
+    read_names( reader, TOURNAMENT_SIZE )
+}
+
+//Tests
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use othello::{OthelloMove, Player};
+    use game::GameSituation;
+
+    fn header( games: u32, names: u16 ) -> Vec<u8>
+    {
+        let mut bytes = vec![ 20, 24, 3, 15 ];
+        bytes.extend( games.to_le_bytes().iter() );
+        bytes.extend( names.to_le_bytes().iter() );
+        bytes.extend( 2023u16.to_le_bytes().iter() );
+        bytes.extend( [ 8, 0, 22, 0 ].iter() );
+        bytes
+    }
+
+    fn game_bytes( moves: &[Coord], black_discs: u8 ) -> Vec<u8>
+    {
+        let mut bytes = vec![ 2, 0, 1, 0, 0, 0, black_discs, 33 ];
+        bytes.extend( moves.iter().map( |coord| ( ( coord.get_row() + 1 ) * 10 + coord.get_col() + 1 ) as u8 ) );
+        bytes.resize( GAME_SIZE, 0 );
+        bytes
+    }
+
+    fn names_bytes( names: &[&str], size: usize ) -> Vec<u8>
+    {
+        let mut bytes = header( 0, names.len() as u16 );
+        for name in names
+        {
+            let mut record = name.chars().map( |c| c as u8 ).collect::<Vec<_>>();
+            record.resize( size, 0 );
+            bytes.extend( record );
+        }
+        bytes
+    }
+
+    //A whole game by a simple rule, with its moves and final position
+    fn played_game() -> ( Vec<Coord>, OthelloSituation )
+    {
+        let mut situation = OthelloSituation::new();
+        let mut moves = Vec::new();
+        let mut ply = 0;
+        while !situation.is_finished()
+        {
+            let options = situation.get_moves().collect::<Vec<_>>();
+            let a_move = options[ ( ply * 3 ) % options.len() ].clone();
+            if let OthelloMove::Coord( coord ) = a_move
+            {
+                moves.push( coord );
+            }
+            situation = situation.copy_apply( a_move ).unwrap();
+            ply += 1;
+        }
+        ( moves, situation )
+    }
+
+    #[test]
+    fn games_are_streamed_and_replayed()
+    {
+        let ( moves, final_situation ) = played_game();
+        let black_discs = ( ( 64 - final_situation.get_score( Player::White ) ) / 2 ) as u8;
+        let mut bytes = header( 2, 0 );
+        bytes.extend( game_bytes( &moves, black_discs ) );
+        bytes.extend( game_bytes( &moves[..10], 40 ) );
+
+        let mut games = WthorGames::new( &bytes[..] ).unwrap();
+        assert_eq!( games.header().created, ( 2024, 3, 15 ) );
+        assert_eq!( games.header().year, 2023 );
+        assert_eq!( games.header().depth, 22 );
+        let game = games.next().unwrap().unwrap();
+        assert_eq!( ( game.tournament, game.black, game.white ), ( 2, 1, 0 ) );
+        assert_eq!( game.theoretical_black_discs, 33 );
+        assert_eq!( game.moves, moves );
+        let record = game.to_record().unwrap();
+        assert_eq!( record.final_situation().unwrap(), final_situation );
+        assert_eq!( games.next().unwrap().unwrap().moves.len(), 10 );
+        assert!( games.next().is_none() );
+    }
+
+    #[test]
+    fn names_are_read()
+    {
+        let players = read_players( &names_bytes( &[ "Tamenori Hideshi", "Caspard Marc" ], PLAYER_SIZE )[..] ).unwrap();
+        let tournaments = read_tournaments( &names_bytes( &[ "Open", "Paris", "Championnat de France" ], TOURNAMENT_SIZE )[..] ).unwrap();
+        assert_eq!( players, vec![ "Tamenori Hideshi", "Caspard Marc" ] );
+        let ( moves, _ ) = played_game();
+        let game = parse_game( &game_bytes( &moves, 32 ) ).unwrap();
+        assert_eq!( game.black_name( &players ), Some( "Caspard Marc" ) );
+        assert_eq!( game.white_name( &players ), Some( "Tamenori Hideshi" ) );
+        assert_eq!( game.tournament_name( &tournaments ), Some( "Championnat de France" ) );
+    }
+
+    #[test]
+    fn corrupt_files_are_rejected()
+    {
+        let mut bytes = header( 2, 0 );
+        bytes.extend( game_bytes( &[], 32 ) );
+        bytes[HEADER_SIZE + 8] = 19;
+        assert!( parse_game( &[ &bytes[HEADER_SIZE..HEADER_SIZE + 8], &[ 5u8 ][..] ].concat() ).is_err() );
+        let mut games = WthorGames::new( &bytes[..] ).unwrap();
+        assert_eq!( games.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData );
+        //The second game is missing
+        assert_eq!( games.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof );
+        assert!( games.next().is_none() );
+
+        let illegal = game_bytes( &[ Coord::from_str( "a1" ).unwrap() ], 32 );
+        assert!( parse_game( &illegal ).unwrap().to_record().is_err() );
+        assert!( WthorGames::new( &bytes[..4] ).is_err() );
+    }
+}
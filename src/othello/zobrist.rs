@@ -0,0 +1,74 @@
+//Zobrist hashing for othello positions. Every (colour, square) pair has a
+//random key and the hash of a position is the xor of the keys of all the
+//discs on the board, xored with SIDE_TO_MOVE when white is to move.
+//Because xor is its own inverse the hash can be updated incrementally when
+//discs are placed or flipped.
+
+use super::Player;
+use super::super::bitboard::{BitBoard, Coord};
+
+//splitmix64, good enough for generating the keys at compile time
+const fn next_random( state: u64 ) -> ( u64, u64 )
+{
+    let state = state.wrapping_add( 0x9e3779b97f4a7c15 );
+    let mut z = state;
+    z = ( z ^ ( z >> 30 ) ).wrapping_mul( 0xbf58476d1ce4e5b9 );
+    z = ( z ^ ( z >> 27 ) ).wrapping_mul( 0x94d049bb133111eb );
+    ( state, z ^ ( z >> 31 ) )
+}
+
+const fn generate_keys() -> ( [[u64; 64]; 2], u64 )
+{
+    let mut keys = [[0u64; 64]; 2];
+    let mut state = 0x0123456789abcdef;
+    let mut colour = 0;
+    while colour < 2
+    {
+        let mut square = 0;
+        while square < 64
+        {
+            let ( new_state, key ) = next_random( state );
+            state = new_state;
+            keys[colour][square] = key;
+            square += 1;
+        }
+        colour += 1;
+    }
+    let ( _, side_to_move ) = next_random( state );
+    ( keys, side_to_move )
+}
+
+const KEYS: ( [[u64; 64]; 2], u64 ) = generate_keys();
+pub const SIDE_TO_MOVE: u64 = KEYS.1;
+
+fn colour_index( player: &Player ) -> usize
+{
+    match *player
+    {
+        Player::Black => 0,
+        Player::White => 1
+    }
+}
+
+pub fn square_key( player: &Player, coord: Coord ) -> u64
+{
+    KEYS.0[ colour_index( player ) ][ coord.get_idx() as usize ]
+}
+
+//Hash of all the discs of one colour
+pub fn board_key( player: &Player, board: BitBoard ) -> u64
+{
+    board.into_iter().fold( 0, |hash, coord| hash ^ square_key( player, coord ) )
+}
+
+//Flipping a disc removes it from one colour and adds it to the other
+pub fn flip_key( flipped: BitBoard ) -> u64
+{
+    board_key( &Player::Black, flipped ) ^ board_key( &Player::White, flipped )
+}
+
+pub fn hash( black_board: BitBoard, white_board: BitBoard, turn: &Player ) -> u64
+{
+    let turn_key = if *turn == Player::White { SIDE_TO_MOVE } else { 0 };
+    board_key( &Player::Black, black_board ) ^ board_key( &Player::White, white_board ) ^ turn_key
+}
@@ -0,0 +1,86 @@
+//The external players against the stub engine of src/bin
+extern crate bit_board;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use bit_board::OthelloGame;
+use bit_board::external::{ExternalError, ExternalPlayer, GtpProtocol};
+use bit_board::game::Player;
+use bit_board::othello::{self, OthelloSituation};
+use bit_board::othello::nboard::NBoardProtocol;
+use bit_board::othello::players::{DummyOthelloPlayer, OthelloAlphaBetaPlayer};
+use bit_board::othello::record::GameResult;
+
+fn stub( mode: &str ) -> Command
+{
+    let mut command = Command::new( env!( "CARGO_BIN_EXE_stub_engine" ) );
+    command.arg( mode );
+    command
+}
+
+fn gtp_stub( mode: &str ) -> ExternalPlayer<GtpProtocol<OthelloSituation>>
+{
+    ExternalPlayer::new( stub( mode ), GtpProtocol::new( OthelloSituation::new() ), Duration::from_secs( 5 ) ).unwrap()
+}
+
+#[test]
+fn gtp_program_plays_whole_games()
+{
+    let mut game = OthelloGame::new( Box::new( OthelloAlphaBetaPlayer::new( 2 ) ), Box::new( gtp_stub( "gtp" ) ) );
+    game.play();
+    assert!( matches!( game.record().result(), Some( GameResult::Discs( _ ) ) ) );
+
+    //Moving first, so the program gets clear_board without a move
+    let mut game = OthelloGame::new( Box::new( gtp_stub( "gtp" ) ), Box::new( DummyOthelloPlayer::new() ) );
+    game.play();
+    assert!( matches!( game.record().result(), Some( GameResult::Discs( _ ) ) ) );
+}
+
+#[test]
+fn nboard_program_plays_a_whole_game()
+{
+    let player = ExternalPlayer::new( stub( "nboard" ), NBoardProtocol::new( 1 ), Duration::from_secs( 5 ) ).unwrap();
+    let mut game = OthelloGame::new( Box::new( DummyOthelloPlayer::new() ), Box::new( player ) );
+    game.play();
+    assert!( matches!( game.record().result(), Some( GameResult::Discs( _ ) ) ) );
+}
+
+#[test]
+fn misbehaving_programs_forfeit()
+{
+    let situation = OthelloSituation::new();
+    let mut crash = gtp_stub( "crash" );
+    assert_eq!( crash.make_move( &situation, None ), None );
+    assert!( matches!( crash.error(), Some( ExternalError::Exited ) ) );
+    //Once lost it stays lost
+    assert_eq!( crash.make_move( &situation, None ), None );
+
+    let mut garbage = gtp_stub( "garbage" );
+    assert_eq!( garbage.make_move( &situation, None ), None );
+    assert!( matches!( garbage.error(), Some( ExternalError::Garbage( _ ) ) ) );
+
+    let mut slow = ExternalPlayer::new( stub( "slow" ), GtpProtocol::new( OthelloSituation::new() ), Duration::from_millis( 300 ) ).unwrap();
+    let start = Instant::now();
+    assert_eq!( slow.make_move( &situation, None ), None );
+    assert!( matches!( slow.error(), Some( ExternalError::Timeout ) ) );
+    assert!( start.elapsed() < Duration::from_secs( 5 ) );
+}
+
+#[test]
+fn games_record_forfeits()
+{
+    let mut game = OthelloGame::new( Box::new( DummyOthelloPlayer::new() ), Box::new( gtp_stub( "crash" ) ) );
+    assert_eq!( game.play(), Some( othello::Player::Black ) );
+    assert_eq!( game.record().result(), Some( &GameResult::Forfeit( othello::Player::Black ) ) );
+
+    //A1 is never legal at the start
+    let mut game = OthelloGame::new( Box::new( gtp_stub( "illegal" ) ), Box::new( DummyOthelloPlayer::new() ) );
+    assert_eq!( game.play(), Some( othello::Player::White ) );
+    assert!( game.record().moves().is_empty() );
+}
+
+#[test]
+fn missing_programs_are_reported()
+{
+    let command = Command::new( "/nonexistent/engine" );
+    assert!( ExternalPlayer::new( command, GtpProtocol::new( OthelloSituation::new() ), Duration::from_secs( 1 ) ).is_err() );
+}